@@ -1,10 +1,17 @@
 use ::enum_primitive_derive::Primitive;
 use num_traits::FromPrimitive;
-use std::{path::Path, str::FromStr};
+use std::str::FromStr;
 
-use crate::{PrmFile, PrmParseError};
+use crate::{
+    lexer::quote, LimitExceeded, PrmErrorKind, PrmFile, PrmParseError, PrmTable, Recovered, Row,
+    RowIter, Rows, ToPrmRows,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Bios {
     Eleepods = 0,
     Beeboorats = 1,
@@ -12,6 +19,9 @@ pub enum Bios {
 }
 
 impl Bios {
+    /// Названия биосов по порядку их номеров
+    pub const NAMES: &'static [&'static str] = &["Eleepods", "Beeboorats", "Zeexes"];
+
     /// Итератор по всем существующим в PRM-файле биосам
     pub fn into_iter() -> impl Iterator<Item = Bios> {
         BiosIntoIterator(0)
@@ -29,10 +39,9 @@ impl Iterator for BiosIntoIterator {
     type Item = Bios;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Bios::from_usize(self.0).and_then(|bios| {
-            self.0 += 1;
-            Some(bios)
-        })
+        let bios = Bios::from_usize(self.0)?;
+        self.0 += 1;
+        Some(bios)
     }
 }
 
@@ -50,6 +59,10 @@ impl FromStr for Bios {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum CultGameType {
     Race = 0,
     Harvest = 1,
@@ -87,51 +100,59 @@ pub trait CultGameTrait {
     fn get_type(&self) -> CultGameType;
 }
 
-pub struct CultGameHarvest {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct CultGameHarvest<S = String> {
     /// Название товара, учитываемого в гонке
-    pub goods_type_name: String,
+    pub goods_type_name: S,
     /// Количество товара (?)
     pub goods_count: u32,
     /// Название конечного пункта назначения для товара
-    pub destination_name: String,
+    pub destination_name: S,
     /// Название испорченного товара
-    pub rotten_goods_type_name: String,
+    pub rotten_goods_type_name: S,
 }
 
-impl CultGameHarvest {
+impl<'a, S: From<&'a str>> CultGameHarvest<S> {
     /// Tries create CultGame from row of the file PRM format
-    fn from_prmrow(row: &str) -> Result<Self, CultGameHarvestParseError> {
-        let mut iter = row.split_whitespace();
-        if iter.next().unwrap() != "HARVEST" {
-            Err(CultGameHarvestParseError::GameTypeName)?;
-        }
-
-        let goods_type_name = iter.next().ok_or(CultGameHarvestParseError::GoodsName)?;
-        let goods_count = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(CultGameHarvestParseError::GoodsCount)?;
+    fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
+        use CultGameHarvestParseError::*;
 
-        let destination_name = iter.next().ok_or(CultGameHarvestParseError::Destination)?;
+        let mut iter = row.fields();
+        if iter.word(GameTypeName)? != "HARVEST" {
+            return Err(iter.error(GameTypeName));
+        }
 
-        let rotten_goods_type_name = iter
-            .next()
-            .ok_or(CultGameHarvestParseError::RottenGoodsName)?;
+        let goods_type_name = iter.word(GoodsName)?;
+        let goods_count = iter.parse(GoodsCount)?;
+        let destination_name = iter.word(Destination)?;
+        let rotten_goods_type_name = iter.word(RottenGoodsName)?;
 
-        if iter.next().is_some() {
-            Err(CultGameHarvestParseError::UnexpectedAdditionalParameter)?
-        }
+        iter.finish(UnexpectedAdditionalParameter)?;
 
         Ok(Self {
-            goods_type_name: goods_type_name.to_owned(),
+            goods_type_name: goods_type_name.into(),
             goods_count,
-            destination_name: destination_name.to_owned(),
-            rotten_goods_type_name: rotten_goods_type_name.to_owned(),
+            destination_name: destination_name.into(),
+            rotten_goods_type_name: rotten_goods_type_name.into(),
         })
     }
 }
 
-impl CultGameTrait for CultGameHarvest {
+impl<S: AsRef<str>> CultGameHarvest<S> {
+    fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            String::from("HARVEST"),
+            quote(self.goods_type_name.as_ref()),
+            self.goods_count.to_string(),
+            quote(self.destination_name.as_ref()),
+            quote(self.rotten_goods_type_name.as_ref()),
+        ]
+    }
+}
+
+impl<S> CultGameTrait for CultGameHarvest<S> {
     fn get_type(&self) -> CultGameType {
         CultGameType::Harvest
     }
@@ -159,58 +180,53 @@ pub enum CultGameRaceParseError {
     UnexpectedAdditionalParameter,
 }
 
-pub struct CultGameRace {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct CultGameRace<S = String> {
     /// Название отправного пункта в гонке
-    pub source_name: String,
+    pub source_name: S,
 
     /// Название конечного пункта в гонке
-    pub destination_name: String,
+    pub destination_name: S,
 
     /// Название отпускаемого товара
-    pub goods_type_beg_name: String,
+    pub goods_type_beg_name: S,
 
     /// Количество отпускаемого товара
     pub goods_count_beg: u32,
 
     /// Название принимаемого товара
-    pub goods_type_end_name: String,
+    pub goods_type_end_name: S,
 
     /// Количество принимаемого товара
     pub goods_count_end: u32,
 
     /// Название испорченного товара
-    pub rotten_goods_type_name: String,
+    pub rotten_goods_type_name: S,
 }
 
-impl CultGameRace {
+impl<'a, S: From<&'a str>> CultGameRace<S> {
     /// Tries create CultGame from row of the file PRM format
-    fn from_prmrow(row: &str) -> Result<Self, CultGameRaceParseError> {
+    fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
         use CultGameRaceParseError::*;
 
-        let mut iter = row.split_whitespace();
-        if iter.next().unwrap() != "RACE" {
-            Err(GameTypeName)?
+        let mut iter = row.fields();
+        if iter.word(GameTypeName)? != "RACE" {
+            return Err(iter.error(GameTypeName));
         }
 
-        let source_name = iter.next().ok_or(SoutceName)?.to_owned();
-        let goods_type_beg_name = iter.next().ok_or(GoodsBeginName)?.to_owned();
-        let goods_count_beg = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(GoodsBeginCount)?;
+        let source_name = iter.word(SoutceName)?.into();
+        let goods_type_beg_name = iter.word(GoodsBeginName)?.into();
+        let goods_count_beg = iter.parse(GoodsBeginCount)?;
 
-        let destination_name = iter.next().ok_or(DestinationName)?.to_owned();
-        let goods_type_end_name = iter.next().ok_or(GoodsEndName)?.to_owned();
-        let goods_count_end = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(GoodsEndgCount)?;
+        let destination_name = iter.word(DestinationName)?.into();
+        let goods_type_end_name = iter.word(GoodsEndName)?.into();
+        let goods_count_end = iter.parse(GoodsEndgCount)?;
 
-        let rotten_goods_type_name = iter.next().ok_or(RottenGoodsName)?.to_owned();
+        let rotten_goods_type_name = iter.word(RottenGoodsName)?.into();
 
-        if iter.next().is_some() {
-            Err(UnexpectedAdditionalParameter)?
-        }
+        iter.finish(UnexpectedAdditionalParameter)?;
 
         Ok(Self {
             source_name,
@@ -224,7 +240,22 @@ impl CultGameRace {
     }
 }
 
-impl CultGameTrait for CultGameRace {
+impl<S: AsRef<str>> CultGameRace<S> {
+    fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            String::from("RACE"),
+            quote(self.source_name.as_ref()),
+            quote(self.goods_type_beg_name.as_ref()),
+            self.goods_count_beg.to_string(),
+            quote(self.destination_name.as_ref()),
+            quote(self.goods_type_end_name.as_ref()),
+            self.goods_count_end.to_string(),
+            quote(self.rotten_goods_type_name.as_ref()),
+        ]
+    }
+}
+
+impl<S> CultGameTrait for CultGameRace<S> {
     fn get_type(&self) -> CultGameType {
         CultGameType::Race
     }
@@ -244,38 +275,54 @@ pub enum CultGameParseError {
     Empty,
 }
 
-pub enum CultGame {
-    Harvest(CultGameHarvest),
-    Race(CultGameRace),
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum CultGame<S = String> {
+    Harvest(CultGameHarvest<S>),
+    Race(CultGameRace<S>),
 }
 
-impl CultGame {
+impl<'a, S: From<&'a str>> CultGame<S> {
     /// Tries create CultGame from row of the file PRM format
-    fn from_prmrow(row: &str) -> Result<Option<Self>, CultGameParseError> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Option<Self>, PrmParseError> {
         use CultGameParseError::*;
 
-        let mut iter = row.split_whitespace();
+        let mut iter = row.fields();
 
-        match (iter.next(), iter.count()) {
-            (None, _) => Err(Empty),
-            (Some("none"), i) if i == 0 => Ok(None),
-            (Some("none"), _) => Err(IncorrectCountProperties(None)),
-            (Some("HARVEST"), i) if i == 4 => {
+        match (iter.next(), row.len().saturating_sub(1)) {
+            (None, _) => Err(row.error(Empty)),
+            (Some("none"), 0) => Ok(None),
+            (Some("none"), _) => Err(row.error(IncorrectCountProperties(None))),
+            (Some("HARVEST"), 4) => {
                 let game = CultGameHarvest::from_prmrow(row)?;
                 Ok(Some(Self::Harvest(game)))
             }
-            (Some("HARVEST"), _) => Err(IncorrectCountProperties(Some(CultGameType::Harvest))),
-            (Some("RACE"), i) if i == 7 => {
+            (Some("HARVEST"), _) => {
+                Err(row.error(IncorrectCountProperties(Some(CultGameType::Harvest))))
+            }
+            (Some("RACE"), 7) => {
                 let game = CultGameRace::from_prmrow(row)?;
                 Ok(Some(Self::Race(game)))
             }
-            (Some("RACE"), _) => Err(IncorrectCountProperties(Some(CultGameType::Race))),
-            (Some(_), _) => Err(IncorrectGameType),
+            (Some("RACE"), _) => Err(row.error(IncorrectCountProperties(Some(CultGameType::Race)))),
+            (Some(_), _) => Err(row.error(IncorrectGameType)),
+        }
+    }
+}
+
+impl<S: AsRef<str>> CultGame<S> {
+    pub(crate) fn to_prmrow(game: Option<&Self>) -> Vec<String> {
+        match game {
+            None => vec![String::from("none")],
+            Some(CultGame::Harvest(game)) => game.to_prmrow(),
+            Some(CultGame::Race(game)) => game.to_prmrow(),
         }
     }
 }
 
-impl CultGameTrait for CultGame {
+impl<S> CultGameTrait for CultGame<S> {
     fn get_type(&self) -> CultGameType {
         match self {
             CultGame::Harvest(_) => CultGameType::Harvest,
@@ -303,9 +350,12 @@ pub enum CultStageParseError {
 }
 
 /// Описание цикла
-pub struct CultStage {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct CultStage<S = String> {
     /// Название цикла
-    pub name: String,
+    pub name: S,
     /// количество нюхи (cirt), необходимое для завершения периода
     pub cirt: u32,
     /// время полураспада в мин
@@ -313,29 +363,23 @@ pub struct CultStage {
     /// коэффициент цен
     pub price: u32,
     /// путь к файлу ресурсов с описанием палитры для текущего цикла
-    pub palette: String,
+    pub palette: S,
 }
 
-impl CultStage {
+impl<'a, S: From<&'a str>> CultStage<S> {
     /// Tries create CultStage from row of the file PRM format
-    fn from_prmrow(row: &str) -> Result<Self, CultStageParseError> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
         use CultStageParseError::*;
 
-        let mut iter = row.split_whitespace();
-        let name = iter
-            .next()
-            .and_then(|s| Some(s.trim_start_matches('"').trim_end_matches('"')))
-            .ok_or(Name)?
-            .to_owned();
+        let mut iter = row.fields();
+        let name = iter.word(Name)?.into();
 
-        let cirt = iter.next().and_then(|s| s.parse().ok()).ok_or(Cirt)?;
-        let time = iter.next().and_then(|s| s.parse().ok()).ok_or(Time)?;
-        let price = iter.next().and_then(|s| s.parse().ok()).ok_or(Cirt)?;
-        let palette = iter.next().ok_or(Name)?.to_owned();
+        let cirt = iter.parse(Cirt)?;
+        let time = iter.parse(Time)?;
+        let price = iter.parse(Price)?;
+        let palette = iter.word(Palette)?.into();
 
-        if iter.next().is_some() {
-            Err(UnexpectedAdditionalParameter)?
-        }
+        iter.finish(UnexpectedAdditionalParameter)?;
 
         Ok(Self {
             name,
@@ -347,21 +391,36 @@ impl CultStage {
     }
 }
 
-pub struct Cult {
+impl<S: AsRef<str>> CultStage<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            quote(self.name.as_ref()),
+            self.cirt.to_string(),
+            self.time.to_string(),
+            self.price.to_string(),
+            quote(self.palette.as_ref()),
+        ]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Cult<S = String> {
     /// Цикл
-    stage: CultStage,
+    pub(crate) stage: CultStage<S>,
     /// Культовая гонка соответсвюущая циклу (если есть)
-    game: Option<CultGame>,
+    pub(crate) game: Option<CultGame<S>>,
 }
 
-impl Cult {
+impl<S> Cult<S> {
     /// Описание цикла
-    pub fn stage(&self) -> &CultStage {
+    pub fn stage(&self) -> &CultStage<S> {
         &self.stage
     }
 
     /// Культовая гонка соответсвюущая циклу (если есть)
-    pub fn game(&self) -> Option<&CultGame> {
+    pub fn game(&self) -> Option<&CultGame<S>> {
         self.game.as_ref()
     }
 }
@@ -370,108 +429,248 @@ impl Cult {
 pub enum BunchParseError {
     #[error("title of the bunch block corrupt")]
     Title,
+    #[error("bios differs from the previous rows of the bunch")]
+    BiosMismatch,
+    #[error("rows of the bunch are separated by another bunch")]
+    SplitBunch,
     #[error("cult stage: {0}")]
     CultStage(#[from] CultStageParseError),
     #[error("cult game: {0}")]
     CultGame(#[from] CultGameParseError),
 }
 
-pub struct Bunch {
+impl From<CultStageParseError> for PrmErrorKind {
+    fn from(from: CultStageParseError) -> Self {
+        Self::Bunch(BunchParseError::CultStage(from))
+    }
+}
+
+impl From<CultGameParseError> for PrmErrorKind {
+    fn from(from: CultGameParseError) -> Self {
+        Self::Bunch(BunchParseError::CultGame(from))
+    }
+}
+
+impl From<CultGameHarvestParseError> for PrmErrorKind {
+    fn from(from: CultGameHarvestParseError) -> Self {
+        CultGameParseError::Harvest(from).into()
+    }
+}
+
+impl From<CultGameRaceParseError> for PrmErrorKind {
+    fn from(from: CultGameRaceParseError) -> Self {
+        CultGameParseError::Race(from).into()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Bunch<S = String> {
     /// Название биоса, к которому относится банч
     pub bios: Bios,
     /// Число периодов в цикле банча
     // pub cycles: u8,
     /// Название эскейва, в котором находится банч
-    pub escave_name: String,
+    pub escave_name: S,
     /// Список циклов и их культовые гонки
-    pub cults: Vec<Cult>,
-    __: (),
+    pub cults: Vec<Cult<S>>,
 }
 
-impl Bunch {
+impl<S> Bunch<S> {
     /// Число периодов в цикле банча
     pub fn cycles(&self) -> usize {
         self.cults.len()
     }
+}
 
-    fn from_prmrow_iter<'a, 'b>(
-        iter: &'b mut impl Iterator<Item = &'a String>,
-    ) -> Result<Self, BunchParseError> {
-        let title = iter.next().ok_or(BunchParseError::Title)?;
+impl<'a, S: From<&'a str>> Bunch<S> {
+    /// Разбирает блок банча.
+    ///
+    /// Ошибка в заголовке прерывает разбор блока, а неверные циклы
+    /// пропускаются с записью ошибки в `diagnostics`.
+    fn from_prmrow_iter(
+        iter: &mut RowIter<'a>,
+        diagnostics: &mut Vec<PrmParseError>,
+    ) -> Result<Self, PrmParseError> {
+        let title = iter.next_or(BunchParseError::Title)?;
 
-        let mut title_iter = title.split_whitespace();
+        let mut title_iter = title.fields();
 
-        let escave_name = title_iter
-            .next()
-            .and_then(|s| if s.trim().len() == 0 { None } else { Some(s) })
-            .ok_or(BunchParseError::Title)?
-            .to_owned();
+        let escave_name = title_iter.word(BunchParseError::Title)?.into();
         let bios = title_iter
             .next()
             .and_then(|bios_index| bios_index.parse().ok())
-            .and_then(|bios_index| Bios::from_u8(bios_index))
-            .ok_or(BunchParseError::Title)?;
+            .and_then(Bios::from_u8)
+            .ok_or_else(|| title_iter.error(BunchParseError::Title))?;
         let cycles = title_iter
             .next()
-            .and_then(|c| c.parse().ok())
-            .and_then(|c| if c == 0 { None } else { Some(c) })
-            .ok_or(BunchParseError::Title)?;
-
-        if title_iter.next().is_some() {
-            Err(BunchParseError::Title)?
+            .and_then(|c| c.parse::<usize>().ok())
+            .filter(|&c| c != 0)
+            .ok_or_else(|| title_iter.error(BunchParseError::Title))?;
+
+        let limit = iter.limits().max_cycles;
+        if cycles > limit {
+            let found = cycles;
+            return Err(title_iter.error(LimitExceeded::Cycles { limit, found }));
         }
 
-        let mut cults: Vec<Cult> = Vec::with_capacity(cycles as usize);
+        title_iter.finish(BunchParseError::Title)?;
 
-        for _ in 0..cycles {
-            let stage = iter.next().map_or_else(
-                || Err(CultStageParseError::Empty),
-                |s| CultStage::from_prmrow(s),
-            )?;
-
-            let game = iter.next().map_or_else(
-                || Err(CultGameParseError::Empty),
-                |s| CultGame::from_prmrow(s),
-            )?;
+        // на каждый цикл приходится две строки файла
+        let mut cults: Vec<Cult<S>> = Vec::with_capacity(cycles.min(iter.len() / 2));
 
-            cults.push(Cult { stage, game });
+        for _ in 0..cycles {
+            let stage = match Self::next_block_row(
+                iter,
+                diagnostics,
+                CultStageParseError::Empty,
+                CultStage::from_prmrow,
+            ) {
+                Some(Ok(stage)) => Some(stage),
+                Some(Err(e)) => {
+                    diagnostics.push(e);
+                    None
+                }
+                None => break,
+            };
+
+            let game = match Self::next_block_row(
+                iter,
+                diagnostics,
+                CultGameParseError::Empty,
+                CultGame::from_prmrow,
+            ) {
+                Some(Ok(game)) => Some(game),
+                Some(Err(e)) => {
+                    diagnostics.push(e);
+                    None
+                }
+                None => break,
+            };
+
+            if let (Some(stage), Some(game)) = (stage, game) {
+                cults.push(Cult { stage, game });
+            }
         }
 
         Ok(Self {
             bios,
             cults,
             escave_name,
-            __: (),
         })
     }
+
+    /// Разбирает следующую строку блока функцией `parse`.
+    ///
+    /// Возвращает `None`, если блок закончился: строк больше нет либо
+    /// следующая строка оказалась заголовком другого банча (такая строка
+    /// не забирается). Ошибка при этом записывается в `diagnostics`.
+    fn next_block_row<T, E: Into<PrmErrorKind>>(
+        iter: &mut RowIter<'a>,
+        diagnostics: &mut Vec<PrmParseError>,
+        kind: E,
+        parse: impl Fn(&'a Row<'a>) -> Result<T, PrmParseError>,
+    ) -> Option<Result<T, PrmParseError>> {
+        let row = match iter.clone().next_or(kind) {
+            Ok(row) => row,
+            Err(e) => {
+                diagnostics.push(e);
+                return None;
+            }
+        };
+
+        let result = parse(row);
+        if result.is_err() && Self::is_title(row) {
+            diagnostics.extend(result.err());
+            return None;
+        }
+
+        iter.next();
+        Some(result)
+    }
+
+    /// Похожа ли строка на заголовок банча: `escave bios cycles`
+    fn is_title(row: &Row<'_>) -> bool {
+        let tokens = row.tokens();
+        tokens.len() == 3
+            && tokens[1].text.parse::<u8>().is_ok()
+            && tokens[2].text.parse::<usize>().is_ok()
+    }
+}
+
+impl<S: AsRef<str>> Bunch<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let title = vec![
+            quote(self.escave_name.as_ref()),
+            (self.bios as u8).to_string(),
+            self.cycles().to_string(),
+        ];
+
+        let mut rows = vec![title];
+        for cult in &self.cults {
+            rows.push(cult.stage.to_prmrow());
+            rows.push(CultGame::to_prmrow(cult.game()));
+        }
+        rows
+    }
 }
 
-pub struct TableBunch {
-    pub bunches: Vec<Bunch>,
-    __: (),
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct TableBunch<S = String> {
+    pub bunches: Vec<Bunch<S>>,
 }
 
-impl TableBunch {
+impl<S> TableBunch<S> {
     pub fn len(&self) -> usize {
         self.bunches.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.bunches.is_empty()
+    }
 }
 
 impl PrmFile for TableBunch {
     fn file_name<'a>() -> &'a str {
         "bunches.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
+impl<'a, S: From<&'a str>> PrmTable<'a> for TableBunch<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
+        let mut diagnostics = vec![];
 
         let mut iter = rows.iter();
         let count = Bios::total();
         let mut bunches = Vec::with_capacity(count);
         for _ in 0..count {
-            bunches.push(Bunch::from_prmrow_iter(&mut iter)?);
+            match Bunch::from_prmrow_iter(&mut iter, &mut diagnostics) {
+                Ok(bunch) => bunches.push(bunch),
+                Err(e) => {
+                    diagnostics.push(e);
+                    // пропускаем строки до заголовка следующего банча
+                    while iter.peek().is_some_and(|row| !Bunch::<S>::is_title(row)) {
+                        iter.next();
+                    }
+                    if iter.len() == 0 {
+                        break;
+                    }
+                }
+            }
         }
 
-        Ok(Self { bunches, __: () })
+        Recovered {
+            table: Self { bunches },
+            diagnostics,
+        }
+    }
+}
+
+impl<S: AsRef<str>> ToPrmRows for TableBunch<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        self.bunches.iter().flat_map(Bunch::to_prmrows).collect()
     }
 }
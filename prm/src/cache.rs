@@ -0,0 +1,221 @@
+//! Кэш разобранных наборов ресурсов (возможность `cache`).
+//!
+//! Разобранный набор хранится в двоичном виде в папке кэша. Ключ записи —
+//! хеш (BLAKE3) содержимого каждого файла набора и версии библиотеки,
+//! поэтому изменение любого файла или обновление библиотеки само делает
+//! старую запись ненужной: она больше не находится по ключу.
+//!
+//! Ненужные записи удаляются сами: в папке хранится не больше
+//! [`ResourceCache::max_entries`] записей, и после создания новой записи
+//! удаляются те, что дольше всех не использовались.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    read_limited, Limits, PrmFile, PrmParseError, ResourceSet, ResourceSource, TableBunch,
+    TableEscave, TableItem, TableMechos, TablePassage, TablePrice, TableSpot, TableTabutask,
+    TableVangersWeight, TableWorld,
+};
+
+/// Расширение файлов записей кэша
+const EXTENSION: &str = "prmcache";
+
+/// Наибольшее число записей в папке кэша по умолчанию
+const MAX_ENTRIES: usize = 16;
+
+/// Версия двоичного вида; поднимается при изменении структур таблиц
+const FORMAT: u32 = 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Parse(#[from] PrmParseError),
+    #[error("{0}")]
+    Encode(#[from] bincode::error::EncodeError),
+}
+
+/// Папка кэша разобранных наборов ресурсов.
+///
+/// Папка может лежать рядом с данными или быть общей для нескольких
+/// папок ресурсов: записи разных наборов не пересекаются.
+#[derive(Debug, Clone)]
+pub struct ResourceCache {
+    path: PathBuf,
+    max_entries: usize,
+}
+
+impl ResourceCache {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            max_entries: MAX_ENTRIES,
+        }
+    }
+
+    /// Наибольшее число записей в папке (по умолчанию 16); только что
+    /// созданная запись остаётся, даже если `max_entries` равно нулю
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Загружает набор из источника `source`, как [`ResourceSet::load`].
+    ///
+    /// Если в кэше есть запись для тех же файлов, набор читается из неё,
+    /// иначе файлы разбираются и запись создаётся. Повреждённая запись
+    /// считается отсутствующей. Файлы читаются с ограничениями
+    /// [`Limits::default`], как в [`ResourceSet::load`].
+    pub fn load<S: ResourceSource + ?Sized>(&self, source: &S) -> Result<ResourceSet, CacheError> {
+        let files = read_files(source)?;
+        let entry = self.path.join(format!("{}.{}", key(&files), EXTENSION));
+
+        match fs::read(&entry) {
+            Ok(bytes) => {
+                let config = bincode::config::standard();
+                if let Ok((set, _)) = bincode::decode_from_slice(&bytes, config) {
+                    // время изменения записи — время её последнего
+                    // использования; папка кэша может быть только для чтения
+                    let _ = fs::File::options()
+                        .write(true)
+                        .open(&entry)
+                        .and_then(|file| file.set_modified(SystemTime::now()));
+                    return Ok(set);
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let set = ResourceSet::load(&files)?;
+        let bytes = bincode::encode_to_vec(&set, bincode::config::standard())?;
+
+        // запись появляется целиком: сначала пишется временный файл
+        // с уникальным именем
+        fs::create_dir_all(&self.path)?;
+        let mut temp = tempfile::NamedTempFile::new_in(&self.path)?;
+        temp.write_all(&bytes)?;
+        temp.persist(&entry).map_err(|e| e.error)?;
+
+        self.prune(&entry)?;
+        Ok(set)
+    }
+
+    /// Удаляет записи, которые дольше всех не использовались, пока вместе
+    /// с записью `keep` их не станет `max_entries`
+    fn prune(&self, keep: &Path) -> io::Result<()> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            let is_entry = path.extension().is_some_and(|ext| ext == EXTENSION);
+            if !is_entry || path == keep {
+                continue;
+            }
+            // запись могли удалить одновременно с нами
+            if let Ok(modified) = entry.metadata().and_then(|meta| meta.modified()) {
+                entries.push((modified, path));
+            }
+        }
+
+        let extra = (entries.len() + 1).saturating_sub(self.max_entries.max(1));
+        entries.sort();
+        for (_, path) in entries.into_iter().take(extra) {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Удаляет все записи кэша
+    pub fn clear(&self) -> io::Result<()> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Имена файлов таблиц набора
+fn file_names() -> [&'static str; 10] {
+    [
+        TableMechos::file_name(),
+        TableItem::file_name(),
+        TableWorld::file_name(),
+        TablePassage::file_name(),
+        TableEscave::file_name(),
+        TableSpot::file_name(),
+        TablePrice::file_name(),
+        TableBunch::file_name(),
+        TableTabutask::file_name(),
+        TableVangersWeight::file_name(),
+    ]
+}
+
+/// Содержимое файлов набора, которые есть в источнике; файл больше
+/// `Limits::max_file_size` — ошибка
+fn read_files<S: ResourceSource + ?Sized>(
+    source: &S,
+) -> Result<HashMap<String, Vec<u8>>, CacheError> {
+    let mut files = HashMap::new();
+    for name in file_names() {
+        let fin = match source.open(name) {
+            Ok(fin) => fin,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let bytes = read_limited(name, fin, &Limits::default())?;
+        files.insert(name.to_owned(), bytes);
+    }
+    Ok(files)
+}
+
+/// Ключ записи: хеш версий библиотеки и двоичного вида и хешей содержимого
+/// всех файлов (отсутствующий файл тоже часть ключа)
+fn key(files: &HashMap<String, Vec<u8>>) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.update(&FORMAT.to_le_bytes());
+
+    for name in file_names() {
+        hasher.update(name.as_bytes());
+        match files.get(name) {
+            Some(bytes) => {
+                hasher.update(&[1]);
+                hasher.update(blake3::hash(bytes).as_bytes());
+            }
+            None => {
+                hasher.update(&[0]);
+            }
+        }
+    }
+
+    hasher.finalize().to_hex().to_string()
+}
@@ -0,0 +1,111 @@
+//! Перевод набора ресурсов ([`ResourceSet`]) в структурированные форматы
+//! и обратно: JSON (возможность `json`), TOML (`toml`) и RON (`ron`).
+//!
+//! Ошибки разбора указывают путь к неверному значению в документе,
+//! например `bunches.bunches[1].cults[0].stage.cirt`.
+
+use crate::ResourceSet;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConvertError {
+    #[error("{path}: {message}")]
+    Deserialize {
+        /// путь к значению в документе (`.` — весь документ)
+        path: String,
+        message: String,
+    },
+    #[error("{path}: {message}")]
+    Serialize { path: String, message: String },
+}
+
+impl ConvertError {
+    #[cfg(any(feature = "json", feature = "toml"))]
+    fn deserialize<E: std::fmt::Display>(e: serde_path_to_error::Error<E>) -> Self {
+        Self::Deserialize {
+            path: e.path().to_string(),
+            message: e.inner().to_string(),
+        }
+    }
+
+    fn serialize<E: std::fmt::Display>(e: serde_path_to_error::Error<E>) -> Self {
+        Self::Serialize {
+            path: e.path().to_string(),
+            message: e.inner().to_string(),
+        }
+    }
+
+    /// Ошибка синтаксиса, найденная до разбора значений
+    #[cfg(any(feature = "toml", feature = "ron"))]
+    fn syntax<E: std::fmt::Display>(e: E) -> Self {
+        Self::Deserialize {
+            path: ".".to_owned(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl ResourceSet {
+    /// Набор в виде JSON-документа
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, ConvertError> {
+        let mut bytes = vec![];
+        let mut serializer = serde_json::Serializer::pretty(&mut bytes);
+        serde_path_to_error::serialize(self, &mut serializer).map_err(ConvertError::serialize)?;
+        Ok(String::from_utf8(bytes).expect("serde_json writes UTF-8"))
+    }
+
+    /// Разбирает набор из JSON-документа
+    #[cfg(feature = "json")]
+    pub fn from_json(text: &str) -> Result<Self, ConvertError> {
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        let set = serde_path_to_error::deserialize(&mut deserializer)
+            .map_err(ConvertError::deserialize)?;
+        deserializer.end().map_err(|e| ConvertError::Deserialize {
+            path: ".".to_owned(),
+            message: e.to_string(),
+        })?;
+        Ok(set)
+    }
+
+    /// Набор в виде TOML-документа
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> Result<String, ConvertError> {
+        let mut buffer = toml::ser::Buffer::new();
+        let serializer = toml::Serializer::pretty(&mut buffer);
+        serde_path_to_error::serialize(self, serializer).map_err(ConvertError::serialize)?;
+        Ok(buffer.to_string())
+    }
+
+    /// Разбирает набор из TOML-документа
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, ConvertError> {
+        let deserializer = toml::Deserializer::parse(text).map_err(ConvertError::syntax)?;
+        serde_path_to_error::deserialize(deserializer).map_err(ConvertError::deserialize)
+    }
+
+    /// Набор в виде RON-документа
+    #[cfg(feature = "ron")]
+    pub fn to_ron(&self) -> Result<String, ConvertError> {
+        let mut text = String::new();
+        let config = ron::ser::PrettyConfig::default();
+        let mut serializer =
+            ron::Serializer::new(&mut text, Some(config)).map_err(ConvertError::syntax)?;
+        serde_path_to_error::serialize(self, &mut serializer).map_err(ConvertError::serialize)?;
+        Ok(text)
+    }
+
+    /// Разбирает набор из RON-документа
+    #[cfg(feature = "ron")]
+    pub fn from_ron(text: &str) -> Result<Self, ConvertError> {
+        let mut deserializer = ron::Deserializer::from_str(text).map_err(ConvertError::syntax)?;
+        let set = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            let message = deserializer.span_error(e.into_inner()).to_string();
+            ConvertError::Deserialize { path, message }
+        })?;
+        deserializer
+            .end()
+            .map_err(|e| ConvertError::syntax(deserializer.span_error(e)))?;
+        Ok(set)
+    }
+}
@@ -0,0 +1,495 @@
+//! Конкретное синтаксическое дерево PRM-файла.
+//!
+//! В отличие от [`Rows`], документ хранит всё содержимое файла: комментарии,
+//! пробелы, пустые строки и переводы строк. Поэтому после правки значений
+//! таблицы файл записывается обратно так, что меняются только изменённые слова.
+
+use std::{io, sync::Arc};
+
+use crate::{
+    encoding,
+    lexer::{self, tokens_of, unquote, LexError, Lexer},
+    Encoding, Limits, PrmFile, PrmHeader, PrmParseError, Recovered, Row, Rows, Span, ToPrmRows,
+    Token,
+};
+
+/// Вид фрагмента строки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceKind {
+    /// Пробелы и табуляции
+    Space,
+    /// Комментарий `// ...` или `/* ... */` (или его часть на этой строке)
+    Comment,
+    /// Значащее слово
+    Token,
+}
+
+/// Фрагмент строки документа
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    pub kind: PieceKind,
+    pub text: String,
+}
+
+/// Строка документа
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    pub pieces: Vec<Piece>,
+    /// Перевод строки как в исходном файле: `"\n"`, `"\r\n"` или пустой
+    /// у последней строки без перевода
+    pub ending: String,
+}
+
+impl Line {
+    pub(crate) fn tokens(&self) -> impl Iterator<Item = &Piece> {
+        self.pieces.iter().filter(|p| p.kind == PieceKind::Token)
+    }
+
+    /// Слова строки с колонками, ссылающиеся на текст строки
+    fn token_list(&self) -> Vec<Token<'_>> {
+        tokens_of(self.pieces.iter().map(|p| (p.kind, p.text.as_str())))
+    }
+
+    pub(crate) fn has_tokens(&self) -> bool {
+        self.tokens().next().is_some()
+    }
+
+    pub(crate) fn token_mut(&mut self, index: usize) -> Option<&mut Piece> {
+        self.pieces
+            .iter_mut()
+            .filter(|p| p.kind == PieceKind::Token)
+            .nth(index)
+    }
+
+    /// Разделитель между словами, принятый в этой строке
+    fn separator(&self) -> &str {
+        let mut seen_token = false;
+        for piece in &self.pieces {
+            match piece.kind {
+                PieceKind::Token => seen_token = true,
+                PieceKind::Space if seen_token => return &piece.text,
+                _ => {}
+            }
+        }
+        " "
+    }
+
+    /// Отступ в начале строки
+    fn indent(&self) -> &str {
+        match self.pieces.first() {
+            Some(p) if p.kind == PieceKind::Space => &p.text,
+            _ => "",
+        }
+    }
+
+    fn push_tokens(&mut self, tokens: &[String], separator: &str) {
+        // слова вставляются сразу за последним словом строки, до комментария
+        let mut at = self
+            .pieces
+            .iter()
+            .rposition(|p| p.kind == PieceKind::Token)
+            .map_or(0, |i| i + 1);
+
+        for token in tokens {
+            if at != 0 {
+                self.pieces.insert(at, piece(PieceKind::Space, separator));
+                at += 1;
+            }
+            self.pieces.insert(at, piece(PieceKind::Token, token));
+            at += 1;
+        }
+    }
+
+    /// Удаляет слова, начиная с `index`, вместе с пробелами перед ними
+    fn truncate_tokens(&mut self, index: usize) {
+        let mut seen = 0;
+        let mut i = 0;
+        while i < self.pieces.len() {
+            if self.pieces[i].kind == PieceKind::Token {
+                if seen >= index {
+                    self.pieces.remove(i);
+                    if i > 0 && self.pieces[i - 1].kind == PieceKind::Space {
+                        self.pieces.remove(i - 1);
+                        i -= 1;
+                    }
+                    continue;
+                }
+                seen += 1;
+            }
+            i += 1;
+        }
+    }
+}
+
+impl std::fmt::Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for piece in &self.pieces {
+            f.write_str(&piece.text)?;
+        }
+        f.write_str(&self.ending)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PrmDocumentError {
+    #[error("{0}")]
+    Parse(#[from] PrmParseError),
+    #[error("line {0}: the document layout doesn't match the table")]
+    LayoutMismatch(usize),
+}
+
+/// PRM-файл без потерь: текст восстанавливается байт в байт
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrmDocument {
+    pub(crate) lines: Vec<Line>,
+    /// кодировка, в которой документ записывается обратно
+    encoding: Encoding,
+    /// файл начинался с UTF-8 BOM
+    bom: bool,
+    /// ошибки лексического разбора (без имени файла в местах)
+    lex_errors: Vec<(Span, LexError)>,
+}
+
+impl PrmDocument {
+    /// Разбирает текст PRM-файла
+    pub fn parse(text: &str) -> Self {
+        let mut lexer = Lexer::default();
+        let lines = lexer::split_lines(text)
+            .map(|(content, ending)| Line {
+                pieces: lexer
+                    .lex_line(content)
+                    .into_iter()
+                    .map(|(kind, text)| piece(kind, text))
+                    .collect(),
+                ending: ending.to_owned(),
+            })
+            .collect();
+
+        Self {
+            lines,
+            encoding: Encoding::Utf8,
+            bom: false,
+            lex_errors: lexer.finish(),
+        }
+    }
+
+    /// Перекодирует и разбирает содержимое файла `file`.
+    ///
+    /// `encoding` — кодировка файла, `None` — определить автоматически;
+    /// документ запишется обратно в той же кодировке. Строки с недопустимыми
+    /// байтами попадают в документ с символом `U+FFFD`, а места этих байтов
+    /// вместе с ошибками лексического разбора — в `diagnostics`.
+    pub fn decode(file: &str, bytes: &[u8], encoding: Option<Encoding>) -> Recovered<Self> {
+        let decoded = encoding::decode(file, bytes, encoding);
+        let mut document = Self::parse(&decoded.text);
+        document.encoding = decoded.encoding;
+        document.bom = decoded.bom;
+
+        let error = LexError::InvalidEncoding(document.encoding);
+        let invalid = decoded.invalid.into_iter().map(|span| (span, error));
+        document.lex_errors.extend(invalid);
+        document.lex_errors.sort_by_key(|(span, _)| span.line);
+        let diagnostics = document.lex_errors(file).collect();
+
+        Recovered {
+            table: document,
+            diagnostics,
+        }
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Заголовок из первой значащей строки; `None`, если она не похожа
+    /// на сигнатуру
+    pub fn header(&self) -> Option<PrmHeader> {
+        let line = self.lines.iter().find(|line| line.has_tokens())?;
+        match line.token_list().as_slice() {
+            [token] => PrmHeader::parse(token.value()),
+            _ => None,
+        }
+    }
+
+    /// Ошибки лексического разбора и перекодирования, в порядке строк
+    pub fn lex_errors<'a>(&'a self, file: &'a str) -> impl Iterator<Item = PrmParseError> + 'a {
+        self.lex_errors.iter().map(move |(span, e)| PrmParseError {
+            span: Span {
+                file: file.to_owned(),
+                ..span.clone()
+            },
+            kind: (*e).into(),
+        })
+    }
+
+    /// Кодировка, в которой документ записывается обратно
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+        self.bom &= encoding == Encoding::Utf8;
+    }
+
+    /// Строки файла без комментариев, как их видят разборщики таблиц;
+    /// слова ссылаются на текст документа
+    pub fn rows(&self, file: &str) -> Rows<'_> {
+        let file: Arc<str> = Arc::from(file);
+        let rows = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.has_tokens())
+            .map(|(index, line)| Row {
+                file: file.clone(),
+                line: index + 1,
+                tokens: line.token_list(),
+            })
+            .collect();
+
+        let eof = match self.lines.last() {
+            Some(line) if line.ending.is_empty() => {
+                let width: usize = line.pieces.iter().map(|p| p.text.chars().count()).sum();
+                (self.lines.len(), width + 1)
+            }
+            Some(_) => (self.lines.len() + 1, 1),
+            None => (1, 1),
+        };
+
+        let lex_errors = self
+            .lex_errors
+            .iter()
+            .map(|(span, e)| {
+                let span = Span {
+                    file: file.to_string(),
+                    ..span.clone()
+                };
+                (span, *e)
+            })
+            .collect();
+
+        Rows {
+            file,
+            rows,
+            eof,
+            encoding: self.encoding,
+            limits: Limits::default(),
+            header: None,
+            lex_errors,
+        }
+    }
+
+    /// Содержимое файла в кодировке документа
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        if self.bom {
+            bytes.extend_from_slice(b"\xEF\xBB\xBF");
+        }
+        bytes.extend_from_slice(&self.encoding.encode(&self.to_string())?);
+        Ok(bytes)
+    }
+
+    /// Записывает документ в `fout` без изменений форматирования,
+    /// в кодировке документа
+    pub fn write_to<W: io::Write>(&self, mut fout: W) -> io::Result<()> {
+        fout.write_all(&self.to_bytes()?)
+    }
+
+    /// Переносит в документ значения таблицы `table`.
+    ///
+    /// Документ сравнивается с таблицей, разобранной из него же, и правятся
+    /// только те слова, значения которых отличаются. Добавленные строки
+    /// таблицы дописываются новыми строками файла, удалённые вычёркиваются
+    /// (строки с комментариями остаются).
+    pub fn update<T: PrmFile + ToPrmRows>(&mut self, table: &T) -> Result<(), PrmDocumentError> {
+        let original = T::parse_document(self)?;
+        let old = original.to_prmrows();
+        let new = table.to_prmrows();
+
+        // номера строк с данными; первая из них — сигнатура
+        let token_lines: Vec<usize> = (0..self.lines.len())
+            .filter(|&i| self.lines[i].has_tokens())
+            .collect();
+        let (signature_line, data_lines) = match token_lines.split_first() {
+            Some((s, d)) => (*s, d),
+            None => return Err(PrmDocumentError::LayoutMismatch(1)),
+        };
+
+        if data_lines.len() != old.len() {
+            return Err(PrmDocumentError::LayoutMismatch(
+                data_lines.last().map_or(signature_line, |&l| l) + 1,
+            ));
+        }
+        for (&line, old_row) in data_lines.iter().zip(&old) {
+            let matches = self.lines[line]
+                .tokens()
+                .zip(old_row)
+                .all(|(piece, value)| same_value(&piece.text, value));
+            if !matches {
+                return Err(PrmDocumentError::LayoutMismatch(line + 1));
+            }
+        }
+
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old_mid = &old[prefix..old.len() - suffix];
+        let new_mid = &new[prefix..new.len() - suffix];
+        let paired = old_mid.len().min(new_mid.len());
+
+        for k in 0..paired {
+            let line = &mut self.lines[data_lines[prefix + k]];
+            let (old_row, new_row) = (&old_mid[k], &new_mid[k]);
+            let count = line.tokens().count();
+
+            for (j, value) in new_row.iter().enumerate().take(count) {
+                if old_row.get(j) != Some(value) {
+                    if let Some(piece) = line.token_mut(j) {
+                        piece.text = value.clone();
+                    }
+                }
+            }
+            if new_row.len() > count && old_row.get(count..) != Some(&new_row[count..]) {
+                let separator = line.separator().to_owned();
+                line.push_tokens(&new_row[count..], &separator);
+            }
+            if new_row.len() < count {
+                line.truncate_tokens(new_row.len());
+            }
+        }
+
+        // лишние строки таблицы удаляются с конца, чтобы не сбить номера
+        for &line in data_lines[prefix + paired..prefix + old_mid.len()]
+            .iter()
+            .rev()
+        {
+            let line_ref = &mut self.lines[line];
+            if line_ref.pieces.iter().any(|p| p.kind == PieceKind::Comment) {
+                line_ref.truncate_tokens(0);
+                if line_ref.pieces.first().map(|p| p.kind) == Some(PieceKind::Space) {
+                    line_ref.pieces.remove(0);
+                }
+            } else {
+                self.lines.remove(line);
+            }
+        }
+
+        if new_mid.len() > paired {
+            let anchor = if prefix + paired > 0 {
+                data_lines[prefix + paired - 1]
+            } else {
+                signature_line
+            };
+            self.insert_rows(anchor, &new_mid[paired..]);
+        }
+
+        Ok(())
+    }
+
+    /// Вставляет новые строки с данными сразу после строки `anchor`
+    fn insert_rows(&mut self, anchor: usize, rows: &[Vec<String>]) {
+        let template = &self.lines[anchor];
+        let indent = template.indent().to_owned();
+        let separator = template.separator().to_owned();
+        let ending = self
+            .lines
+            .iter()
+            .map(|l| l.ending.as_str())
+            .find(|e| !e.is_empty())
+            .unwrap_or("\n")
+            .to_owned();
+
+        if self.lines[anchor].ending.is_empty() {
+            self.lines[anchor].ending = ending.clone();
+        }
+        let last_ending = if anchor + 1 == self.lines.len() {
+            String::new()
+        } else {
+            ending.clone()
+        };
+
+        for (i, row) in rows.iter().enumerate() {
+            let mut line = Line {
+                pieces: vec![],
+                ending: if i + 1 == rows.len() {
+                    last_ending.clone()
+                } else {
+                    ending.clone()
+                },
+            };
+            if !indent.is_empty() {
+                line.pieces.push(piece(PieceKind::Space, &indent));
+            }
+            line.push_tokens(row, &separator);
+            self.lines.insert(anchor + 1 + i, line);
+        }
+    }
+}
+
+impl std::fmt::Display for PrmDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Документ записывается кодировкой и текстом; текст файла с UTF-8 BOM
+/// начинается с символа `U+FEFF`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DocumentText {
+    encoding: Encoding,
+    text: String,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PrmDocument {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bom = if self.bom { "\u{feff}" } else { "" };
+        DocumentText {
+            encoding: self.encoding,
+            text: format!("{}{}", bom, self),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PrmDocument {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let DocumentText { encoding, text } = DocumentText::deserialize(deserializer)?;
+        let mut document = match text.strip_prefix('\u{feff}') {
+            Some(text) => Self::parse(text),
+            None => Self::parse(&text),
+        };
+        document.bom = text.starts_with('\u{feff}');
+        document.set_encoding(encoding);
+        Ok(document)
+    }
+}
+
+pub(crate) fn piece(kind: PieceKind, text: &str) -> Piece {
+    Piece {
+        kind,
+        text: text.to_owned(),
+    }
+}
+
+/// Совпадает ли слово файла со значением, полученным из таблицы
+/// (с точностью до кавычек и записи чисел)
+fn same_value(token: &str, value: &str) -> bool {
+    if token == value || unquote(token) == unquote(value) {
+        return true;
+    }
+    match (token.parse::<i64>(), value.parse::<i64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
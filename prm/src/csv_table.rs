@@ -0,0 +1,797 @@
+//! Выгрузка таблиц в CSV для правки в электронных таблицах и загрузка
+//! обратно (возможность `csv`).
+//!
+//! Ячейки строки CSV разбираются теми же разборщиками, что и строки
+//! PRM-файла, поэтому ошибки те же, а их место — строка и колонка CSV.
+
+use std::{borrow::Cow, io, sync::Arc};
+
+use indexmap::IndexMap;
+
+use num_traits::FromPrimitive;
+
+use crate::{
+    lexer::unquote, Bios, Bunch, BunchParseError, Cult, CultGame, CultGameParseError, CultStage,
+    Encoding, Item, Limits, Location, LocationKind, LocationParseError, Mechos, MechosCounters,
+    MechosGroup, MechosParseError, Passage, Price, PrmParseError, Recovered, Row, Rows, TableBunch,
+    TableEscave, TableItem, TableMechos, TablePassage, TablePrice, TableSpot, TableTabutask,
+    TableVangersWeight, TableWorld, Tabutask, Token, VangersWeightParseError, World,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CsvError {
+    #[error("{0}")]
+    Csv(#[from] ::csv::Error),
+    #[error("{0}")]
+    Parse(#[from] PrmParseError),
+    #[error("{file}:1:{column}: expected column `{expected}`")]
+    Header {
+        file: String,
+        column: usize,
+        expected: &'static str,
+    },
+    #[error("{file}:1:{column}: unexpected column")]
+    UnexpectedColumn { file: String, column: usize },
+}
+
+/// Вид значений колонки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Text,
+    Number,
+    /// Номер варианта из списка; в PRM и CSV записывается номер,
+    /// в книге XLSX — название варианта
+    Enum(&'static [&'static str]),
+}
+
+/// Колонка таблицы CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvColumn {
+    pub name: &'static str,
+    pub kind: CellKind,
+}
+
+const fn text(name: &'static str) -> CsvColumn {
+    CsvColumn {
+        name,
+        kind: CellKind::Text,
+    }
+}
+
+const fn number(name: &'static str) -> CsvColumn {
+    CsvColumn {
+        name,
+        kind: CellKind::Number,
+    }
+}
+
+const fn choice(name: &'static str, variants: &'static [&'static str]) -> CsvColumn {
+    CsvColumn {
+        name,
+        kind: CellKind::Enum(variants),
+    }
+}
+
+/// Таблица, которую можно выгрузить в CSV и загрузить обратно
+pub trait CsvTable: Sized {
+    /// Колонки CSV
+    const COLUMNS: &'static [CsvColumn];
+
+    /// Строки CSV без заголовка
+    fn to_csv_records(&self) -> Vec<Vec<String>>;
+
+    /// Собирает таблицу из строк CSV, не останавливаясь на ошибках: слова
+    /// строки — её ячейки, колонки слов — номера колонок CSV. Неверные
+    /// строки (или блоки) пропускаются, а ошибки собираются
+    /// в [`Recovered::diagnostics`]
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self>;
+
+    /// Собирает таблицу из строк CSV; ошибка — первая из найденных
+    fn from_csv_rows(rows: &Rows<'_>) -> Result<Self, PrmParseError> {
+        Self::from_csv_rows_recovering(rows).into_result()
+    }
+
+    /// Записывает таблицу в `fout` в виде CSV с заголовком
+    fn write_csv<W: io::Write>(&self, fout: W) -> Result<(), CsvError> {
+        let mut writer = ::csv::Writer::from_writer(fout);
+        writer.write_record(Self::COLUMNS.iter().map(|column| column.name))?;
+        for record in self.to_csv_records() {
+            writer.write_record(&record)?;
+        }
+        writer.flush().map_err(::csv::Error::from)?;
+        Ok(())
+    }
+
+    fn to_csv_string(&self) -> Result<String, CsvError> {
+        let mut bytes = vec![];
+        self.write_csv(&mut bytes)?;
+        Ok(String::from_utf8(bytes).expect("table values are UTF-8"))
+    }
+
+    /// Читает таблицу из CSV-файла `file`; заголовок должен совпадать
+    /// с именами колонок [`CsvTable::COLUMNS`]
+    fn read_csv<R: io::Read>(file: &str, fin: R) -> Result<Self, CsvError> {
+        let mut reader = ::csv::Reader::from_reader(fin);
+
+        let header = reader.headers()?;
+        check_header::<Self>(file, &header.iter().collect::<Vec<_>>())?;
+
+        let records = reader.records().collect::<Result<Vec<_>, ::csv::Error>>()?;
+        let rows = csv_rows(
+            file,
+            records.iter().map(|record| {
+                let line = record.position().map_or(0, |p| p.line() as usize);
+                (line, record.iter().collect())
+            }),
+        );
+
+        Ok(Self::from_csv_rows(&rows)?)
+    }
+
+    fn from_csv_str(file: &str, text: &str) -> Result<Self, CsvError> {
+        Self::read_csv(file, text.as_bytes())
+    }
+}
+
+/// Проверяет, что заголовок `header` совпадает с колонками таблицы
+pub(crate) fn check_header<T: CsvTable>(file: &str, header: &[&str]) -> Result<(), CsvError> {
+    for (index, column) in T::COLUMNS.iter().enumerate() {
+        if header.get(index) != Some(&column.name) {
+            return Err(CsvError::Header {
+                file: file.to_owned(),
+                column: index + 1,
+                expected: column.name,
+            });
+        }
+    }
+    if header.len() > T::COLUMNS.len() {
+        return Err(CsvError::UnexpectedColumn {
+            file: file.to_owned(),
+            column: T::COLUMNS.len() + 1,
+        });
+    }
+    Ok(())
+}
+
+/// Строки таблицы из ячеек: номер строки -> ячейки по порядку колонок
+pub(crate) fn csv_rows<'a>(
+    file: &str,
+    records: impl IntoIterator<Item = (usize, Vec<&'a str>)>,
+) -> Rows<'a> {
+    let file: Arc<str> = Arc::from(file);
+    let rows = records
+        .into_iter()
+        .map(|(line, record)| Row {
+            file: file.clone(),
+            line,
+            tokens: record
+                .into_iter()
+                .enumerate()
+                .map(|(index, cell)| Token {
+                    column: index + 1,
+                    text: Cow::Borrowed(cell),
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let eof = (rows.last().map_or(1, |row| row.line) + 1, 1);
+    Rows {
+        file,
+        rows,
+        eof,
+        encoding: Encoding::Utf8,
+        limits: Limits::default(),
+        header: None,
+        lex_errors: vec![],
+    }
+}
+
+/// Ячейки строки CSV из диапазона `range` как слова PRM-строки
+fn cells<'a>(row: &Row<'a>, range: std::ops::Range<usize>) -> Row<'a> {
+    Row {
+        file: row.file.clone(),
+        line: row.line,
+        tokens: row.tokens[range].to_vec(),
+    }
+}
+
+/// Строка без пустых ячеек (для необязательных колонок)
+fn non_empty(mut row: Row<'_>) -> Row<'_> {
+    row.tokens.retain(|token| !token.text.is_empty());
+    row
+}
+
+/// Значения PRM-строки, записанной для файла, без кавычек
+fn unquoted(row: Vec<String>) -> Vec<String> {
+    row.iter().map(|value| unquote(value).to_owned()).collect()
+}
+
+/// `car.prm`: колонка `group` — номер группы счётчиков ([`MechosGroup`]),
+/// к которой относится мехос; поле `box` разбито на четыре колонки
+impl CsvTable for TableMechos {
+    const COLUMNS: &'static [CsvColumn] = &[
+        choice("group", MechosGroup::NAMES),
+        text("name"),
+        choice("type", crate::Type::NAMES),
+        number("price_buy"),
+        number("price_sell"),
+        number("box_1"),
+        number("box_2"),
+        number("box_3"),
+        number("box_4"),
+        number("speed"),
+        number("armor"),
+        number("energy"),
+        number("energy_delta"),
+        number("energy_drop"),
+        number("drop_time"),
+        number("fire"),
+        number("water"),
+        number("oxygen"),
+        number("fly"),
+        number("damage"),
+        number("teleport"),
+    ];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        self.groups()
+            .zip(&self.mechoses)
+            .map(|(group, mechos)| {
+                let mut record = vec![(group as u8).to_string()];
+                record.extend(unquoted(mechos.to_prmrow()));
+                record
+            })
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut counters = MechosCounters::default();
+        let mut mechoses = Vec::with_capacity(rows.rows.len());
+        let mut diagnostics = vec![];
+
+        // группы идут подряд: мехосы записываются в файл по порядку групп
+        let mut current = MechosGroup::Main;
+        for row in rows.iter() {
+            let mut fields = row.fields();
+            let group = fields
+                .parse::<u8, _>(MechosParseError::CounterGroup)
+                .ok()
+                .and_then(MechosGroup::from_u8)
+                .filter(|&group| group as u8 >= current as u8)
+                .ok_or_else(|| fields.error(MechosParseError::CounterGroup));
+            let group = match group {
+                Ok(group) => group,
+                Err(e) => {
+                    diagnostics.push(e);
+                    continue;
+                }
+            };
+
+            current = group;
+            match Mechos::from_prmrow(&cells(row, 1..row.len())) {
+                Ok(mechos) => {
+                    *counters.get_mut(group) += 1;
+                    mechoses.push(mechos);
+                }
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { counters, mechoses },
+            diagnostics,
+        }
+    }
+}
+
+/// `item.prm`
+impl CsvTable for TableItem {
+    const COLUMNS: &'static [CsvColumn] = &[
+        text("name"),
+        number("type"),
+        number("steeler_full"),
+        number("steeler_empty"),
+        number("size"),
+        number("count"),
+        number("param1"),
+        number("param2"),
+    ];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        self.items
+            .iter()
+            .map(|item| unquoted(item.to_prmrow()))
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut items = vec![];
+        let mut diagnostics = vec![];
+        for row in rows.iter() {
+            match Item::from_prmrow(row) {
+                Ok(value) => items.push(value),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { items },
+            diagnostics,
+        }
+    }
+}
+
+/// `worlds.prm`
+impl CsvTable for TableWorld {
+    const COLUMNS: &'static [CsvColumn] = &[text("name"), number("width"), number("height")];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        self.worlds
+            .iter()
+            .map(|world| unquoted(world.to_prmrow()))
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut worlds = vec![];
+        let mut diagnostics = vec![];
+        for row in rows.iter() {
+            match World::from_prmrow(row) {
+                Ok(value) => worlds.push(value),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { worlds },
+            diagnostics,
+        }
+    }
+}
+
+/// `price.prm` в длинном виде: одна строка на товар эскейва
+impl CsvTable for TablePrice {
+    const COLUMNS: &'static [CsvColumn] =
+        &[text("escave"), text("item"), number("buy"), number("sell")];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        let mut records = vec![];
+        for (escave, prices) in &self.prices {
+            for price in prices {
+                let mut record = vec![escave.clone()];
+                record.extend(unquoted(price.to_prmrow()));
+                records.push(record);
+            }
+        }
+        records
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut prices: IndexMap<String, Vec<Price>> = IndexMap::new();
+        let mut diagnostics = vec![];
+        for row in rows.iter() {
+            let escave = row.tokens()[0].value().to_owned();
+            match Price::from_prmrow(&cells(row, 1..row.len())) {
+                Ok(price) => prices.entry(escave).or_default().push(price),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { prices },
+            diagnostics,
+        }
+    }
+}
+
+/// `tabutask.prm` в длинном виде: одна строка на задание эскейва
+impl CsvTable for TableTabutask {
+    const COLUMNS: &'static [CsvColumn] = &[
+        text("escave"),
+        number("cash"),
+        number("luck"),
+        number("cycle"),
+        number("target"),
+        number("work"),
+        text("item"),
+        number("count"),
+    ];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        let mut records = vec![];
+        for (escave, tabutasks) in &self.tabutasks {
+            for tabutask in tabutasks {
+                let mut record = vec![escave.clone()];
+                record.extend(unquoted(tabutask.to_prmrow()));
+                records.push(record);
+            }
+        }
+        records
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut tabutasks: IndexMap<String, Vec<Tabutask>> = IndexMap::new();
+        let mut diagnostics = vec![];
+        for row in rows.iter() {
+            let escave = row.tokens()[0].value().to_owned();
+            match Tabutask::from_prmrow(&cells(row, 1..row.len())) {
+                Ok(tabutask) => tabutasks.entry(escave).or_default().push(tabutask),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { tabutasks },
+            diagnostics,
+        }
+    }
+}
+
+/// Блоки `escaves.prm` и `spot.prm` в длинном виде: одна строка на товар,
+/// первая строка блока повторяется; у блока без товаров колонки товара пусты
+const BLOCK_COLUMNS: &[CsvColumn] = &[
+    text("name"),
+    text("world"),
+    number("pos_x"),
+    number("pos_y"),
+    text("personal_item"),
+    text("goods_item"),
+    text("goods_destination"),
+];
+
+/// Строки CSV блока из его строк для файла: заголовка, товаров и `none`
+fn block_records(prmrows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let mut prmrows = prmrows.into_iter().map(unquoted);
+    let title = prmrows.next().unwrap_or_default();
+    let goods: Vec<_> = prmrows.filter(|row| row.len() == 2).collect();
+
+    if goods.is_empty() {
+        let mut record = title;
+        record.extend(vec![String::new(); 2]);
+        return vec![record];
+    }
+
+    goods
+        .into_iter()
+        .map(|goods| {
+            let mut record = title.clone();
+            record.extend(goods);
+            record
+        })
+        .collect()
+}
+
+/// Блоки торговых точек вида `kind`: строки CSV подряд с одним названием
+/// относятся к одному блоку и повторяют его первую строку
+fn locations_from_csv(rows: &Rows<'_>, kind: LocationKind) -> Recovered<Vec<Location>> {
+    let mut locations = vec![];
+    let mut diagnostics = vec![];
+    let mut title_row = None;
+    for row in rows.iter() {
+        if let Err(e) = location_from_csv(&mut locations, &mut title_row, row, kind) {
+            diagnostics.push(e);
+        }
+    }
+
+    Recovered {
+        table: locations,
+        diagnostics,
+    }
+}
+
+/// Добавляет строку CSV к последнему блоку или начинает ею новый;
+/// `title_row` — первая строка последнего блока
+fn location_from_csv<'r>(
+    locations: &mut Vec<Location>,
+    title_row: &mut Option<&'r Row<'r>>,
+    row: &'r Row<'r>,
+    kind: LocationKind,
+) -> Result<(), PrmParseError> {
+    use LocationParseError::*;
+
+    let goods_cells = non_empty(cells(row, 5..row.len()));
+    let goods = match goods_cells.len() {
+        0 => None,
+        1 => return Err(goods_cells.error(GoodsLine)),
+        _ => Some(Location::goods_from_prmrow(&goods_cells)?),
+    };
+
+    let name = row.tokens()[0].value();
+    match *title_row {
+        Some(title) if title.tokens()[0].value() == name => {
+            let mut fields = row.fields();
+            for expected in title.fields().take(5) {
+                if fields.next() != Some(expected) {
+                    return Err(fields.error(TitleMismatch));
+                }
+            }
+        }
+        _ if locations.iter().any(|location| location.name == name) => {
+            return Err(row.error(SplitBlock));
+        }
+        _ => {
+            // пустая колонка `personal_item` — личной вещи нет
+            let mut title_cells = cells(row, 0..5);
+            if title_cells.tokens[4].text.is_empty() {
+                title_cells.tokens.pop();
+            }
+            locations.push(Location::from_title_prmrow(&title_cells, kind)?);
+            *title_row = Some(row);
+        }
+    }
+
+    let location = locations.last_mut().expect("location is pushed above");
+    location.goods.extend(goods);
+    Ok(())
+}
+
+/// `escaves.prm`
+impl CsvTable for TableEscave {
+    const COLUMNS: &'static [CsvColumn] = BLOCK_COLUMNS;
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        self.escaves
+            .iter()
+            .flat_map(|escave| block_records(escave.to_prmrows()))
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let Recovered {
+            table: escaves,
+            diagnostics,
+        } = locations_from_csv(rows, LocationKind::Escave);
+
+        Recovered {
+            table: Self { escaves },
+            diagnostics,
+        }
+    }
+}
+
+/// `spot.prm`
+impl CsvTable for TableSpot {
+    const COLUMNS: &'static [CsvColumn] = BLOCK_COLUMNS;
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        self.spots
+            .iter()
+            .flat_map(|spot| block_records(spot.to_prmrows()))
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let Recovered {
+            table: spots,
+            diagnostics,
+        } = locations_from_csv(rows, LocationKind::Spot);
+
+        Recovered {
+            table: Self { spots },
+            diagnostics,
+        }
+    }
+}
+
+/// `passages.prm`
+impl CsvTable for TablePassage {
+    const COLUMNS: &'static [CsvColumn] = &[
+        text("name"),
+        text("world_source"),
+        text("world_destination"),
+        number("pos_x"),
+        number("pos_y"),
+    ];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        self.passages
+            .iter()
+            .map(|passage| unquoted(passage.to_prmrow()))
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut passages = vec![];
+        let mut diagnostics = vec![];
+        for row in rows.iter() {
+            match Passage::from_prmrow(row) {
+                Ok(value) => passages.push(value),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { passages },
+            diagnostics,
+        }
+    }
+}
+
+/// `bunches.prm` в длинном виде: одна строка на цикл банча. Колонки
+/// культовой гонки — объединение полей `RACE` и `HARVEST`; у гонки
+/// `HARVEST` пусты `source`, `goods_end` и `goods_end_count`, у цикла
+/// без гонки (`none`) пусты все колонки после `game`
+impl CsvTable for TableBunch {
+    const COLUMNS: &'static [CsvColumn] = &[
+        text("escave"),
+        choice("bios", Bios::NAMES),
+        text("stage"),
+        number("cirt"),
+        number("time"),
+        number("price"),
+        text("palette"),
+        text("game"),
+        text("source"),
+        text("goods_begin"),
+        number("goods_begin_count"),
+        text("destination"),
+        text("goods_end"),
+        number("goods_end_count"),
+        text("rotten_goods"),
+    ];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        let mut records = vec![];
+        for bunch in &self.bunches {
+            for cult in &bunch.cults {
+                let mut record = vec![bunch.escave_name.clone(), (bunch.bios as u8).to_string()];
+                record.extend(unquoted(cult.stage.to_prmrow()));
+
+                let game = unquoted(CultGame::to_prmrow(cult.game()));
+                let mut game_cells = vec![String::new(); 7];
+                match game.len() {
+                    // HARVEST goods count destination rotten
+                    5 => {
+                        game_cells[1..4].clone_from_slice(&game[1..4]);
+                        game_cells[6] = game[4].clone();
+                    }
+                    8 => game_cells.clone_from_slice(&game[1..]),
+                    _ => {}
+                }
+                record.push(game[0].clone());
+                record.extend(game_cells);
+                records.push(record);
+            }
+        }
+        records
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        let mut bunches = vec![];
+        let mut diagnostics = vec![];
+        for row in rows.iter() {
+            if let Err(e) = bunch_from_csv(&mut bunches, row) {
+                diagnostics.push(e);
+            }
+        }
+
+        Recovered {
+            table: Self { bunches },
+            diagnostics,
+        }
+    }
+}
+
+/// Добавляет цикл из строки CSV к последнему банчу или начинает им новый
+fn bunch_from_csv(bunches: &mut Vec<Bunch>, row: &Row<'_>) -> Result<(), PrmParseError> {
+    let mut fields = row.fields();
+    let escave_name = fields.word(BunchParseError::Title)?;
+    let bios = fields
+        .parse::<u8, _>(BunchParseError::Title)
+        .ok()
+        .and_then(Bios::from_u8)
+        .ok_or_else(|| fields.error(BunchParseError::Title))?;
+
+    // строки банча идут подряд и повторяют его эскейв и биос
+    let continues = match bunches.last() {
+        Some(bunch) if bunch.escave_name == escave_name => {
+            if bunch.bios != bios {
+                return Err(fields.error(BunchParseError::BiosMismatch));
+            }
+            true
+        }
+        _ if bunches.iter().any(|b| b.escave_name == escave_name) => {
+            return Err(row.error(BunchParseError::SplitBunch));
+        }
+        _ => false,
+    };
+
+    let stage = CultStage::from_prmrow(&cells(row, 2..7))?;
+    // пустые колонки — поля, которых у гонки этого вида нет
+    let game_cells = cells(row, 7..row.len());
+    let game_row = non_empty(game_cells.clone());
+    if game_row.is_empty() {
+        return Err(game_cells.error(CultGameParseError::Empty));
+    }
+    let game = CultGame::from_prmrow(&game_row)?;
+
+    if !continues {
+        bunches.push(Bunch {
+            bios,
+            escave_name: escave_name.to_owned(),
+            cults: vec![],
+        });
+    }
+    let bunch = bunches.last_mut().expect("bunch is pushed above");
+    bunch.cults.push(Cult { stage, game });
+    Ok(())
+}
+
+/// `vangers.prm`: общее число вангеров записано в первой строке CSV,
+/// в остальных строках колонка `vangers_total` пуста
+impl CsvTable for TableVangersWeight {
+    const COLUMNS: &'static [CsvColumn] =
+        &[number("vangers_total"), text("world"), number("weight")];
+
+    fn to_csv_records(&self) -> Vec<Vec<String>> {
+        let total = self.vangers_total.to_string();
+        if self.weights.is_empty() {
+            return vec![vec![total, String::new(), String::new()]];
+        }
+
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(index, (world, weight))| {
+                let total = if index == 0 {
+                    total.clone()
+                } else {
+                    String::new()
+                };
+                vec![total, world.clone(), weight.to_string()]
+            })
+            .collect()
+    }
+
+    fn from_csv_rows_recovering(rows: &Rows<'_>) -> Recovered<Self> {
+        use VangersWeightParseError::*;
+
+        let mut diagnostics = vec![];
+        let total = rows
+            .iter()
+            .next_or(VangersTotal)
+            .and_then(|first| first.fields().parse::<u32, _>(VangersTotal));
+        let total = total.map_err(|e| diagnostics.push(e)).ok();
+
+        let mut weights = IndexMap::new();
+        for row in rows.iter() {
+            let mut fields = row.fields();
+            match (fields.next(), total) {
+                (Some(cell), Some(total)) if !cell.is_empty() && cell != total.to_string() => {
+                    diagnostics.push(fields.error(VangersTotal));
+                    continue;
+                }
+                _ => {}
+            }
+
+            let weight = non_empty(cells(row, 1..row.len()));
+            if weight.is_empty() {
+                continue;
+            }
+
+            let mut fields = weight.fields();
+            let weight = fields.word(RelativeWeight).and_then(|world| {
+                let value = fields.parse(RelativeWeight)?;
+                Ok((world.to_owned(), value))
+            });
+            match weight {
+                Ok((world, _)) if weights.contains_key(&world) => {
+                    diagnostics.push(cells(row, 1..2).error(DuplicateWorld));
+                }
+                Ok((world, value)) => {
+                    weights.insert(world, value);
+                }
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self {
+                vangers_total: total.unwrap_or_default(),
+                weights,
+            },
+            diagnostics,
+        }
+    }
+}
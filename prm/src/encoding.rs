@@ -0,0 +1,130 @@
+use std::{borrow::Cow, io};
+
+use crate::Span;
+
+/// Кодировка PRM-файла.
+///
+/// Оригинальные ресурсы игры записаны в DOS- (CP866) и Windows-кодировке
+/// (CP1251), моды часто сохраняют в UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Cp866,
+    Cp1251,
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Cp866 => "CP866",
+            Encoding::Cp1251 => "CP1251",
+        })
+    }
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+impl Encoding {
+    /// Угадывает кодировку по содержимому файла.
+    ///
+    /// Корректный UTF-8 считается UTF-8; иначе выбирается та из CP866
+    /// и CP1251, в которой получается больше русских букв.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(UTF8_BOM) || std::str::from_utf8(bytes).is_ok() {
+            return Encoding::Utf8;
+        }
+
+        let score = |encoding: Encoding| {
+            encoding
+                .codec()
+                .decode_without_bom_handling(bytes)
+                .0
+                .chars()
+                .filter(|c| matches!(c, 'а'..='я' | 'А'..='Я' | 'ё' | 'Ё'))
+                .count()
+        };
+
+        if score(Encoding::Cp1251) > score(Encoding::Cp866) {
+            Encoding::Cp1251
+        } else {
+            Encoding::Cp866
+        }
+    }
+
+    /// Перекодирует текст в байты этой кодировки.
+    ///
+    /// Символы, которых нет в кодировке, дают ошибку `InvalidData`.
+    pub fn encode<'a>(&self, text: &'a str) -> io::Result<Cow<'a, [u8]>> {
+        let (bytes, _, unmappable) = self.codec().encode(text);
+        if unmappable {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("text can't be represented in {}", self),
+            ));
+        }
+        Ok(bytes)
+    }
+
+    fn codec(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Encoding::Utf8 => encoding_rs::UTF_8,
+            Encoding::Cp866 => encoding_rs::IBM866,
+            Encoding::Cp1251 => encoding_rs::WINDOWS_1251,
+        }
+    }
+}
+
+/// Текст файла после перекодирования
+pub(crate) struct Decoded {
+    pub text: String,
+    pub encoding: Encoding,
+    /// файл начинался с UTF-8 BOM
+    pub bom: bool,
+    /// места первых недопустимых байтов в строках
+    pub invalid: Vec<Span>,
+}
+
+/// Перекодирует содержимое файла `file`; `None` — определить кодировку
+/// автоматически.
+///
+/// Недопустимые байты заменяются символом `U+FFFD`, а для каждой такой
+/// строки запоминается место ошибки.
+pub(crate) fn decode(file: &str, bytes: &[u8], encoding: Option<Encoding>) -> Decoded {
+    let encoding = encoding.unwrap_or_else(|| Encoding::detect(bytes));
+    let bom = encoding == Encoding::Utf8 && bytes.starts_with(UTF8_BOM);
+    let bytes = if bom { &bytes[UTF8_BOM.len()..] } else { bytes };
+
+    let mut text = String::with_capacity(bytes.len());
+    let mut invalid = vec![];
+
+    // '\n' одинаково кодируется во всех поддерживаемых кодировках
+    for (index, line) in bytes.split_inclusive(|&b| b == b'\n').enumerate() {
+        let (line, had_errors) = encoding.codec().decode_without_bom_handling(line);
+        if had_errors {
+            let column = line
+                .chars()
+                .position(|c| c == char::REPLACEMENT_CHARACTER)
+                .map_or(1, |i| i + 1);
+            invalid.push(Span {
+                file: file.to_owned(),
+                line: index + 1,
+                column,
+                token: String::new(),
+            });
+        }
+        text.push_str(&line);
+    }
+
+    Decoded {
+        text,
+        encoding,
+        bom,
+        invalid,
+    }
+}
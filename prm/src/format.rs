@@ -0,0 +1,264 @@
+//! Приведение PRM-файла к каноническому виду.
+//!
+//! Слова идущих подряд строк одного вида выравниваются по колонкам (как
+//! в оригинальном `car.prm`): текст прижимается влево, числа — вправо.
+//! Вид строки — число слов и то, какие из них числа; строки комментариев
+//! блок не прерывают, строка другого вида начинает новый. Лишние пробелы,
+//! пустые строки подряд и пробелы в конце строк убираются, сигнатура
+//! записывается в виде [`PrmHeader`], переводы строк приводятся к первому
+//! встреченному в файле. Комментарии сохраняются.
+//!
+//! [`PrmHeader`]: crate::PrmHeader
+
+use crate::{cst::piece, Line, PieceKind, PrmDocument};
+
+/// Ширина и выравнивание колонки
+#[derive(Debug, Clone, Copy, Default)]
+struct Column {
+    width: usize,
+    numeric: bool,
+}
+
+impl PrmDocument {
+    /// Переписывает документ в каноническом виде
+    pub fn format(&mut self) {
+        let ending = self
+            .lines
+            .iter()
+            .map(|l| l.ending.as_str())
+            .find(|e| !e.is_empty())
+            .unwrap_or("\n")
+            .to_owned();
+
+        let in_comment = comment_continuations(&self.lines);
+        let mut lines = Vec::with_capacity(self.lines.len());
+        for (line, in_comment) in self.lines.drain(..).zip(in_comment) {
+            let line = normalize(line);
+            let blank = line.pieces.is_empty() && !in_comment;
+            let previous_blank = lines.last().is_none_or(|l: &Line| l.pieces.is_empty());
+            // пустые строки в начале файла и подряд не нужны
+            if blank && previous_blank {
+                continue;
+            }
+            lines.push(line);
+        }
+        while matches!(lines.last(), Some(line) if line.pieces.is_empty()) {
+            lines.pop();
+        }
+        self.lines = lines;
+
+        let data: Vec<usize> = (0..self.lines.len())
+            .filter(|&i| self.lines[i].has_tokens())
+            .collect();
+        let mut data = data.into_iter();
+
+        if let Some(signature) = data.next() {
+            let header = self.header();
+            if let (Some(header), Some(token)) = (header, self.lines[signature].token_mut(0)) {
+                token.text = header.to_string();
+            }
+        }
+
+        for block in blocks(&self.lines, data) {
+            let columns = columns(block.iter().map(|&i| &self.lines[i]));
+            for i in block {
+                align(&mut self.lines[i], &columns);
+            }
+        }
+
+        for line in &mut self.lines {
+            line.ending = ending.clone();
+        }
+    }
+
+    /// Копия документа в каноническом виде
+    pub fn formatted(&self) -> Self {
+        let mut document = self.clone();
+        document.format();
+        document
+    }
+
+    /// Документ уже в каноническом виде
+    pub fn is_formatted(&self) -> bool {
+        self.unformatted_line().is_none()
+    }
+
+    /// Номер первой строки (с единицы), которую изменит [`format`];
+    /// `None`, если документ уже в каноническом виде
+    ///
+    /// [`format`]: PrmDocument::format
+    pub fn unformatted_line(&self) -> Option<usize> {
+        let formatted = self.formatted();
+        let differs = self
+            .lines
+            .iter()
+            .zip(&formatted.lines)
+            .position(|(a, b)| a != b);
+
+        match differs {
+            Some(index) => Some(index + 1),
+            None if self.lines.len() != formatted.lines.len() => {
+                Some(self.lines.len().min(formatted.lines.len()) + 1)
+            }
+            None => None,
+        }
+    }
+}
+
+/// Для каждой строки: начинается ли она внутри блочного комментария
+fn comment_continuations(lines: &[Line]) -> Vec<bool> {
+    let mut open = false;
+    lines
+        .iter()
+        .map(|line| {
+            let starts_open = open;
+            for piece in line.pieces.iter().filter(|p| p.kind == PieceKind::Comment) {
+                let text = piece.text.as_str();
+                if open {
+                    open = !text.ends_with("*/");
+                } else if text.starts_with("/*") {
+                    open = text.len() < 4 || !text.ends_with("*/");
+                }
+            }
+            starts_open
+        })
+        .collect()
+}
+
+/// Убирает пробелы по краям строки и сводит пробелы между фрагментами к одному
+fn normalize(mut line: Line) -> Line {
+    while matches!(line.pieces.last(), Some(p) if p.kind == PieceKind::Space) {
+        line.pieces.pop();
+    }
+    if let Some(last) = line.pieces.last_mut() {
+        if last.kind == PieceKind::Comment {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+            if last.text.is_empty() {
+                line.pieces.pop();
+            }
+        }
+    }
+    while matches!(line.pieces.first(), Some(p) if p.kind == PieceKind::Space) {
+        line.pieces.remove(0);
+    }
+
+    let mut pieces = Vec::with_capacity(line.pieces.len());
+    for piece in line.pieces {
+        if piece.kind == PieceKind::Space {
+            continue;
+        }
+        if !pieces.is_empty() {
+            pieces.push(self::piece(PieceKind::Space, " "));
+        }
+        pieces.push(piece);
+    }
+    line.pieces = pieces;
+    line
+}
+
+/// Слова строки идут подряд, без комментариев между ними и перед ними
+fn is_alignable(line: &Line) -> bool {
+    let last = line.pieces.iter().rposition(|p| p.kind == PieceKind::Token);
+    match last {
+        Some(last) => line.pieces[..last]
+            .iter()
+            .all(|p| p.kind != PieceKind::Comment),
+        None => false,
+    }
+}
+
+/// Вид строки: какие из её слов — числа
+fn shape(line: &Line) -> Vec<bool> {
+    line.tokens()
+        .map(|token| token.text.parse::<f64>().is_ok())
+        .collect()
+}
+
+/// Блоки выравниваемых строк `data` (номера строк по порядку): идущие
+/// подряд строки одного вида; строки из одного слова не выравниваются
+fn blocks(lines: &[Line], data: impl Iterator<Item = usize>) -> Vec<Vec<usize>> {
+    let mut blocks = vec![];
+    let mut block: Vec<usize> = vec![];
+    let mut block_shape = vec![];
+
+    for i in data {
+        let line = &lines[i];
+        let shape = if is_alignable(line) {
+            shape(line)
+        } else {
+            vec![]
+        };
+        if shape != block_shape && !block.is_empty() {
+            blocks.push(std::mem::take(&mut block));
+        }
+        if shape.len() >= 2 {
+            block.push(i);
+        }
+        block_shape = shape;
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Колонки блока строк одного вида
+fn columns<'a>(lines: impl Iterator<Item = &'a Line>) -> Vec<Column> {
+    let mut columns: Vec<Column> = vec![];
+    for line in lines {
+        if columns.is_empty() {
+            columns = shape(line)
+                .into_iter()
+                .map(|numeric| Column { width: 0, numeric })
+                .collect();
+        }
+        for (column, token) in columns.iter_mut().zip(line.tokens()) {
+            column.width = column.width.max(token.text.chars().count());
+        }
+    }
+    columns
+}
+
+/// Расставляет слова строки по колонкам; комментарий в конце строки
+/// отделяется одним пробелом
+fn align(line: &mut Line, columns: &[Column]) {
+    let split = line
+        .pieces
+        .iter()
+        .rposition(|p| p.kind == PieceKind::Token)
+        .map_or(0, |i| i + 1);
+    let comments: Vec<_> = line.pieces.drain(split..).collect();
+    let tokens: Vec<_> = line
+        .pieces
+        .drain(..)
+        .filter(|p| p.kind == PieceKind::Token)
+        .collect();
+
+    // отступ, который ещё не записан: перед словом, прижатым вправо,
+    // он растёт на недостающую ширину
+    let mut space = 0;
+    for (i, (token, column)) in tokens.into_iter().zip(columns).enumerate() {
+        let width = token.text.chars().count();
+        let last = i + 1 == columns.len();
+        let fill = column.width.saturating_sub(width);
+
+        if column.numeric {
+            space += fill;
+        }
+        if space > 0 {
+            line.pieces
+                .push(piece(PieceKind::Space, &" ".repeat(space)));
+        }
+        line.pieces.push(token);
+
+        space = if column.numeric || last { 1 } else { fill + 1 };
+    }
+
+    for comment in comments {
+        if comment.kind == PieceKind::Comment {
+            line.pieces.push(piece(PieceKind::Space, " "));
+            line.pieces.push(comment);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+/// Семейство сигнатуры файлов параметров игры
+pub const PRM_FAMILY: &str = "uniVang-ParametersFile";
+
+/// Заголовок PRM-файла: первая строка вида `<семейство>_Ver_<версия>`,
+/// например `uniVang-ParametersFile_Ver_1`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrmHeader {
+    /// Семейство сигнатуры (`uniVang-ParametersFile`)
+    pub family: String,
+    /// Номер версии формата
+    pub version: u32,
+}
+
+impl PrmHeader {
+    /// Заголовок `uniVang-ParametersFile` версии `version`
+    pub fn univang(version: u32) -> Self {
+        Self {
+            family: PRM_FAMILY.to_owned(),
+            version,
+        }
+    }
+
+    /// Разбирает сигнатуру; `None`, если она не похожа на `<семейство>_Ver_<версия>`
+    pub fn parse(signature: &str) -> Option<Self> {
+        let (family, version) = signature.rsplit_once("_Ver_")?;
+        if family.is_empty() || !version.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(Self {
+            family: family.to_owned(),
+            version: version.parse().ok()?,
+        })
+    }
+
+    /// Заголовок относится к семейству `uniVang-ParametersFile`
+    pub fn is_univang(&self) -> bool {
+        self.family == PRM_FAMILY
+    }
+}
+
+/// Заголовок, который пишет и понимает оригинальная игра
+impl Default for PrmHeader {
+    fn default() -> Self {
+        Self::univang(1)
+    }
+}
+
+impl std::fmt::Display for PrmHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_Ver_{}", self.family, self.version)
+    }
+}
@@ -1,6 +1,4 @@
-use std::path::Path;
-
-use crate::{PrmFile, PrmParseError};
+use crate::{lexer::quote, PrmFile, PrmParseError, PrmTable, Recovered, Row, Rows, ToPrmRows};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ItemParseError {
@@ -29,50 +27,47 @@ pub enum ItemParseError {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
 pub struct SteelerType {
     pub full: i32,
     pub empty: i32,
 }
 
-pub struct Item {
-    pub name: String,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Item<S = String> {
+    pub name: S,
     pub r#type: i32,
     pub steeler: SteelerType,
     pub size: u32,
     pub count: u32,
     pub param1: i32,
     pub param2: i32,
-    __: (),
 }
 
-impl Item {
-    fn from_prmrow(row: &str) -> Result<Self, ItemParseError> {
+impl<'a, S: From<&'a str>> Item<S> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
         use ItemParseError::*;
 
-        let mut iter = row.split_whitespace();
-        let name = iter.next().ok_or(Name)?.to_owned();
-        let r#type = iter.next().and_then(|s| s.parse().ok()).ok_or(Type)?;
+        let mut iter = row.fields();
+        let name = iter.word(Name)?.into();
+        let r#type = iter.parse(Type)?;
 
         let steeler = {
-            let full = iter
-                .next()
-                .and_then(|s| s.parse().ok())
-                .ok_or(SteelerFull)?;
-            let empty = iter
-                .next()
-                .and_then(|s| s.parse().ok())
-                .ok_or(SteelerFull)?;
+            let full = iter.parse(SteelerFull)?;
+            let empty = iter.parse(SteelerEmpty)?;
             SteelerType { full, empty }
         };
 
-        let size = iter.next().and_then(|s| s.parse().ok()).ok_or(Size)?;
-        let count = iter.next().and_then(|s| s.parse().ok()).ok_or(Count)?;
-        let param1 = iter.next().and_then(|s| s.parse().ok()).ok_or(Param1)?;
-        let param2 = iter.next().and_then(|s| s.parse().ok()).ok_or(Param2)?;
+        let size = iter.parse(Size)?;
+        let count = iter.parse(Count)?;
+        let param1 = iter.parse(Param1)?;
+        let param2 = iter.parse(Param2)?;
 
-        if iter.next().is_some() {
-            Err(UnexpectedAdditionalParameter)?
-        }
+        iter.finish(UnexpectedAdditionalParameter)?;
 
         Ok(Self {
             name,
@@ -82,44 +77,93 @@ impl Item {
             count,
             param1,
             param2,
-            __: (),
         })
     }
 }
 
-pub struct TableItem {
-    pub items: Vec<Item>,
-    __: (),
+impl<S: AsRef<str>> Item<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            quote(self.name.as_ref()),
+            self.r#type.to_string(),
+            self.steeler.full.to_string(),
+            self.steeler.empty.to_string(),
+            self.size.to_string(),
+            self.count.to_string(),
+            self.param1.to_string(),
+            self.param2.to_string(),
+        ]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct TableItem<S = String> {
+    pub items: Vec<Item<S>>,
 }
 
 impl PrmFile for TableItem {
     fn file_name<'a>() -> &'a str {
         "item.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
+impl<'a, S: From<&'a str>> PrmTable<'a> for TableItem<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
+        let mut diagnostics = vec![];
 
         let mut iter = rows.iter();
-        let count = iter
-            .next()
-            .and_then(|s| s.trim().parse().ok())
-            .ok_or(ItemParseError::Title)?;
+        let count = iter.next_or(ItemParseError::Title).and_then(|row| {
+            let mut fields = row.fields();
+            let count = fields.parse(ItemParseError::Title)?;
+            fields.finish(ItemParseError::Title)?;
+            Ok(count)
+        });
+
+        // при испорченном заголовке предметами считаются все остальные строки
+        let count = match count {
+            Ok(count) => count,
+            Err(e) => {
+                diagnostics.push(e);
+                iter.len()
+            }
+        };
 
-        let mut items = Vec::with_capacity(count);
+        // счётчик из файла не больше числа оставшихся строк, которое
+        // ограничено `Limits::max_rows`
+        let mut items = Vec::with_capacity(count.min(iter.len()));
 
         for _ in 0..count {
-            let item = iter
-                .next()
-                .and_then(|s| Some(Item::from_prmrow(&s)))
-                .ok_or(ItemParseError::ExpectedAdditionalItem)??;
-            items.push(item);
+            let row = match iter.next_or(ItemParseError::ExpectedAdditionalItem) {
+                Ok(row) => row,
+                Err(e) => {
+                    diagnostics.push(e);
+                    break;
+                }
+            };
+            match Item::from_prmrow(row) {
+                Ok(item) => items.push(item),
+                Err(e) => diagnostics.push(e),
+            }
         }
 
         // if iter.next().is_some() {
         //     Err(ItemParseError::UnexpectedAdditionalItem)?
         // }
 
-        Ok(Self { items, __: () })
+        Recovered {
+            table: Self { items },
+            diagnostics,
+        }
+    }
+}
+
+impl<S: AsRef<str>> ToPrmRows for TableItem<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let title = vec![self.items.len().to_string()];
+        std::iter::once(title)
+            .chain(self.items.iter().map(Item::to_prmrow))
+            .collect()
     }
 }
@@ -0,0 +1,219 @@
+//! Лексический разбор PRM-файла: общий для документа ([`PrmDocument`])
+//! и всех разборщиков таблиц.
+//!
+//! Строка делится на пробелы, комментарии и слова. Комментарии бывают
+//! строчными (`// ...`) и блочными (`/* ... */`, в том числе несколько
+//! на одной строке и на несколько строк). Слово, начинающееся с кавычки,
+//! продолжается до закрывающей кавычки и может содержать пробелы
+//! и `//` (например, `"Eleepod Bath"`).
+//!
+//! [`PrmDocument`]: crate::PrmDocument
+
+use std::borrow::Cow;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_till, take_till1, take_until, take_while1},
+    character::complete::{char, one_of},
+    combinator::{map, not, recognize, rest},
+    multi::many1_count,
+    sequence::{pair, terminated, tuple},
+    IResult,
+};
+
+use crate::{Encoding, PieceKind, Span, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum LexError {
+    #[error("invalid byte sequence for {0} encoding")]
+    InvalidEncoding(Encoding),
+    #[error("unterminated comment `/*`")]
+    UnterminatedComment,
+    #[error("unterminated quoted string")]
+    UnterminatedString,
+}
+
+/// Лексема, на которые делится строка
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lexeme {
+    Space,
+    Comment,
+    /// `/*` без закрывающего `*/` на этой строке
+    OpenComment,
+    Token,
+    /// кавычка без закрывающей кавычки на этой строке
+    OpenString,
+}
+
+type LexResult<'a> = IResult<&'a str, (Lexeme, &'a str)>;
+
+/// Слово без кавычек: до пробела или начала комментария
+fn bare_word(input: &str) -> IResult<&str, &str> {
+    recognize(many1_count(alt((
+        take_till1(|c: char| c.is_whitespace() || c == '/'),
+        terminated(tag("/"), not(one_of("/*"))),
+    ))))(input)
+}
+
+/// Очередная лексема вне блочного комментария
+fn lexeme(input: &str) -> LexResult<'_> {
+    alt((
+        map(take_while1(char::is_whitespace), |s| (Lexeme::Space, s)),
+        map(recognize(pair(tag("//"), rest)), |s| (Lexeme::Comment, s)),
+        map(
+            recognize(tuple((tag("/*"), take_until("*/"), tag("*/")))),
+            |s| (Lexeme::Comment, s),
+        ),
+        map(recognize(pair(tag("/*"), rest)), |s| {
+            (Lexeme::OpenComment, s)
+        }),
+        map(
+            recognize(tuple((char('"'), take_till(|c| c == '"'), char('"')))),
+            |s| (Lexeme::Token, s),
+        ),
+        map(recognize(pair(char('"'), rest)), |s| {
+            (Lexeme::OpenString, s)
+        }),
+        map(bare_word, |s| (Lexeme::Token, s)),
+    ))(input)
+}
+
+/// Продолжение блочного комментария: до `*/` включительно либо до конца строки
+fn comment_tail(input: &str) -> LexResult<'_> {
+    alt((
+        map(recognize(pair(take_until("*/"), tag("*/"))), |s| {
+            (Lexeme::Comment, s)
+        }),
+        map(rest, |s| (Lexeme::OpenComment, s)),
+    ))(input)
+}
+
+/// Делит текст на строки: (содержимое, перевод строки)
+pub(crate) fn split_lines(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.split_inclusive('\n').map(|line| {
+        if let Some(content) = line.strip_suffix("\r\n") {
+            (content, "\r\n")
+        } else if let Some(content) = line.strip_suffix('\n') {
+            (content, "\n")
+        } else {
+            (line, "")
+        }
+    })
+}
+
+/// Лексер, разбирающий файл построчно
+#[derive(Debug, Default)]
+pub(crate) struct Lexer {
+    /// номер текущей строки (с единицы)
+    line: usize,
+    /// место начала незакрытого комментария `/*`
+    comment: Option<Span>,
+    errors: Vec<(Span, LexError)>,
+}
+
+impl Lexer {
+    /// Разбивает на фрагменты очередную строку файла (без перевода строки);
+    /// фрагменты ссылаются на текст строки
+    pub fn lex_line<'a>(&mut self, line: &'a str) -> Vec<(PieceKind, &'a str)> {
+        self.line += 1;
+
+        let mut pieces = vec![];
+        let mut column = 1;
+        let mut rest = line;
+
+        while !rest.is_empty() {
+            let parsed = if self.comment.is_some() {
+                comment_tail(rest)
+            } else {
+                lexeme(rest)
+            };
+            // `bare_word` принимает всё, что не подошло остальным лексемам
+            let (tail, (lexeme, text)) = parsed.unwrap_or(("", (Lexeme::Token, rest)));
+
+            let kind = match lexeme {
+                Lexeme::Space => PieceKind::Space,
+                Lexeme::Comment => {
+                    // закрывает блочный комментарий, если он был открыт
+                    self.comment = None;
+                    PieceKind::Comment
+                }
+                Lexeme::OpenComment => {
+                    if self.comment.is_none() {
+                        self.comment = Some(self.span(column, "/*"));
+                    }
+                    PieceKind::Comment
+                }
+                Lexeme::Token => PieceKind::Token,
+                Lexeme::OpenString => {
+                    let error = (self.span(column, text), LexError::UnterminatedString);
+                    self.errors.push(error);
+                    PieceKind::Token
+                }
+            };
+
+            column += text.chars().count();
+            pieces.push((kind, text));
+            rest = tail;
+        }
+
+        pieces
+    }
+
+    /// Ошибки разбора в порядке строк; незакрытый к концу файла
+    /// комментарий `/*` указывает на своё начало
+    pub fn finish(mut self) -> Vec<(Span, LexError)> {
+        if let Some(span) = self.comment.take() {
+            self.errors.push((span, LexError::UnterminatedComment));
+            self.errors.sort_by_key(|(span, _)| span.line);
+        }
+        self.errors
+    }
+
+    /// Место без имени файла: оно подставляется при получении строк
+    fn span(&self, column: usize, token: &str) -> Span {
+        Span {
+            file: String::new(),
+            line: self.line,
+            column,
+            token: token.to_owned(),
+        }
+    }
+}
+
+/// Слова строки вместе с их колонками, без копирования текста
+pub(crate) fn tokens_of<'a>(
+    pieces: impl IntoIterator<Item = (PieceKind, &'a str)>,
+) -> Vec<Token<'a>> {
+    let mut tokens = vec![];
+    let mut column = 1;
+    for (kind, text) in pieces {
+        if kind == PieceKind::Token {
+            tokens.push(Token {
+                column,
+                text: Cow::Borrowed(text),
+            });
+        }
+        column += text.chars().count();
+    }
+    tokens
+}
+
+/// Значение слова без окружающих кавычек
+pub(crate) fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// Записывает значение так, чтобы лексер прочитал его одним словом
+pub(crate) fn quote(value: &str) -> String {
+    if value.is_empty()
+        || value.contains(char::is_whitespace)
+        || value.contains("//")
+        || value.contains("/*")
+    {
+        format!("\"{}\"", value)
+    } else {
+        value.to_owned()
+    }
+}
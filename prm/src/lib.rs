@@ -1,97 +1,602 @@
+//! Чтение и запись файлов параметров (`*.prm`) игры Вангеры.
+//!
+//! # Возможность `serde`
+//!
+//! С включённой возможностью `serde` все таблицы (`Table*`) и их строки
+//! реализуют `Serialize` и `Deserialize`. Имена полей совпадают с именами
+//! полей структур (`r#type` и `r#box` записываются как `type` и `box`),
+//! перечисления `Bios`, `Type` и `CultGameType` записываются строками
+//! в `snake_case` (`"eleepods"`, `"raffa"`, `"race"`), а `CultGame` —
+//! объектом с полем `type` (`"harvest"` или `"race"`) рядом с полями игры.
+//!
+//! Возможности `json`, `toml` и `ron` (включают `serde`) добавляют перевод
+//! всего набора ресурсов ([`ResourceSet`]) в эти форматы и обратно,
+//! возможность `schema` (включает `serde`) — JSON Schema этого вида
+//! (`json_schemas`, `ResourceSet::json_schema`).
+//!
+//! Возможность `csv` добавляет выгрузку отдельных таблиц в CSV и загрузку
+//! обратно (`CsvTable`), возможность `xlsx` (включает `csv`) — запись
+//! всего набора ресурсов в книгу XLSX с листом на каждую таблицу и чтение
+//! обратно.
+//!
+//! Возможность `sqlite` добавляет выгрузку набора ресурсов в базу SQLite
+//! со связями между таблицами (`ResourceSet::to_sqlite`), возможность
+//! `cache` — кэш разобранных наборов, ключ которого — хеши содержимого
+//! файлов (`ResourceCache`).
+
 use std::{
-    io::{BufRead, BufReader},
+    borrow::Cow,
+    io::{BufRead, Read},
     path::Path,
+    str::FromStr,
+    sync::Arc,
 };
 
-use nom::FindSubstring;
-
 mod bunch;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+mod convert;
+mod cst;
+#[cfg(feature = "csv")]
+mod csv_table;
+mod encoding;
 mod escave;
+mod format;
+mod header;
 mod item;
+mod lexer;
+mod limits;
+mod location;
 mod mechos;
 mod passage;
 mod price;
+mod resources;
+#[cfg(feature = "schema")]
+mod schema;
+mod source;
 mod spot;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod tabutask;
 mod vangers;
 mod world;
+mod writer;
+#[cfg(feature = "xlsx")]
+mod xlsx;
 
 pub use bunch::*;
+#[cfg(feature = "cache")]
+pub use cache::*;
+#[cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+pub use convert::*;
+pub use cst::*;
+#[cfg(feature = "csv")]
+pub use csv_table::*;
+pub use encoding::Encoding;
 pub use escave::*;
+pub use header::*;
+pub use indexmap::IndexMap;
 pub use item::*;
+pub use lexer::LexError;
+use lexer::{tokens_of, Lexer};
+pub use limits::*;
+pub use location::*;
 pub use mechos::*;
 pub use passage::*;
 pub use price::*;
+pub use resources::*;
+#[cfg(feature = "schema")]
+pub use schema::*;
+pub use source::*;
 pub use spot::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
 pub use tabutask::*;
 pub use vangers::*;
 pub use world::*;
+pub use writer::*;
+#[cfg(feature = "xlsx")]
+pub use xlsx::*;
 
-pub fn read_without_comments<R: BufRead>(fin: R) -> Vec<String> {
-    let mut is_comment_block = false;
-    let mut lines = vec![];
+/// Место в исходном PRM-файле
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Имя файла
+    pub file: String,
+    /// Номер строки (с единицы) в исходном файле, до удаления комментариев.
+    /// Равен нулю, если ошибка относится ко всему файлу.
+    pub line: usize,
+    /// Номер колонки (с единицы), в символах
+    pub column: usize,
+    /// Токен, на котором произошла ошибка (пустой, если токена нет)
+    pub token: String,
+}
 
-    for line in fin.lines() {
-        if let Ok(mut line) = line {
-            loop {
-                let cline = line.trim();
-                if cline.is_empty() {
-                    break;
-                }
+impl Span {
+    fn file(file: &str) -> Self {
+        Self {
+            file: file.to_owned(),
+            line: 0,
+            column: 0,
+            token: String::new(),
+        }
+    }
+}
 
-                if !is_comment_block {
-                    if let Some(start_pos) = cline.find_substring("/*") {
-                        line = match (&cline[start_pos..]).find_substring("*/") {
-                            Some(end_pos) => {
-                                format!(
-                                    "{}{}",
-                                    &cline[..start_pos],
-                                    &cline[start_pos + end_pos + 2..]
-                                )
-                            }
-                            None => {
-                                is_comment_block = true;
-                                String::from(&cline[..start_pos])
-                            }
-                        };
-                        continue;
-                    }
-                } else {
-                    if let Some(end_pos) = cline.find_substring("*/") {
-                        line = String::from(&cline[end_pos + 2..]);
-                        is_comment_block = false;
-                        continue;
-                    } else {
-                        break;
-                    }
-                }
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.file)
+        } else {
+            write!(f, "{}:{}:{}", self.file, self.line, self.column)
+        }
+    }
+}
 
-                if let Some(pos_end) = cline.find_substring("//") {
-                    line = String::from(&cline[..pos_end]);
-                    continue;
-                }
+/// Значащее слово строки PRM-файла.
+///
+/// Слова, полученные [`Rows::tokenize`], ссылаются на исходный текст.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// Номер колонки (с единицы), в символах
+    pub column: usize,
+    /// Слово как оно записано в файле (с кавычками, если они были)
+    pub text: Cow<'a, str>,
+}
+
+impl Token<'_> {
+    /// Значение слова: текст без окружающих кавычек
+    pub fn value(&self) -> &str {
+        lexer::unquote(&self.text)
+    }
+
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            column: self.column,
+            text: Cow::Owned(self.text.into_owned()),
+        }
+    }
+}
+
+/// Строка PRM-файла без комментариев, разбитая на слова
+#[derive(Debug, Clone)]
+pub struct Row<'a> {
+    file: Arc<str>,
+    line: usize,
+    tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Row<'a> {
+    /// Имя файла, из которого прочитана строка
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Номер строки (с единицы) в исходном файле
+    pub fn line(&self) -> usize {
+        self.line
+    }
 
-                lines.push(cline.to_owned());
-                break;
+    pub fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+
+    /// Количество слов в строке
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Строка состоит из единственного слова `word`
+    pub fn is(&self, word: &str) -> bool {
+        self.tokens.len() == 1 && self.tokens[0].value() == word
+    }
+
+    /// Итератор по словам строки, умеющий указать место ошибки
+    pub fn fields(&self) -> Fields<'_> {
+        Fields { row: self, pos: 0 }
+    }
+
+    pub fn into_owned(self) -> Row<'static> {
+        Row {
+            file: self.file,
+            line: self.line,
+            tokens: self.tokens.into_iter().map(Token::into_owned).collect(),
+        }
+    }
+
+    /// Место строки целиком (её первое слово)
+    pub fn span(&self) -> Span {
+        self.token_span(0)
+    }
+
+    /// Ошибка, относящаяся к строке целиком
+    pub fn error<E: Into<PrmErrorKind>>(&self, kind: E) -> PrmParseError {
+        PrmParseError {
+            span: self.span(),
+            kind: kind.into(),
+        }
+    }
+
+    fn token_span(&self, index: usize) -> Span {
+        let (column, token) = match self.tokens.get(index) {
+            Some(t) => (t.column, t.text.to_string()),
+            None => (
+                self.tokens
+                    .last()
+                    .map_or(1, |t| t.column + t.text.chars().count()),
+                String::new(),
+            ),
+        };
+
+        Span {
+            file: self.file.to_string(),
+            line: self.line,
+            column,
+            token,
+        }
+    }
+}
+
+impl std::fmt::Display for Row<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
             }
+            f.write_str(&token.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Итератор по словам строки.
+///
+/// Помнит последнее выданное слово, поэтому ошибку разбора можно привязать
+/// к нему (или к концу строки, если слов не хватило).
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+    row: &'a Row<'a>,
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    /// Ошибка разбора последнего выданного слова
+    pub fn error<E: Into<PrmErrorKind>>(&self, kind: E) -> PrmParseError {
+        PrmParseError {
+            span: self.row.token_span(self.pos.saturating_sub(1)),
+            kind: kind.into(),
+        }
+    }
+
+    /// Следующее слово либо ошибка `kind`, если слов не осталось
+    pub fn word<E: Into<PrmErrorKind>>(&mut self, kind: E) -> Result<&'a str, PrmParseError> {
+        self.next().ok_or_else(|| self.error(kind))
+    }
+
+    /// Следующее слово, приведённое к типу `T`, либо ошибка `kind`
+    pub fn parse<T: FromStr, E: Into<PrmErrorKind>>(
+        &mut self,
+        kind: E,
+    ) -> Result<T, PrmParseError> {
+        self.next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| self.error(kind))
+    }
+
+    /// Ошибка `kind`, если в строке остались слова
+    pub fn finish<E: Into<PrmErrorKind>>(&mut self, kind: E) -> Result<(), PrmParseError> {
+        match self.next() {
+            Some(_) => Err(self.error(kind)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.row.tokens.get(self.pos);
+        if self.pos <= self.row.tokens.len() {
+            self.pos += 1;
+        }
+        token.map(Token::value)
+    }
+}
+
+/// Строки PRM-файла без комментариев.
+///
+/// `Rows<'a>`, полученные [`Rows::tokenize`], ссылаются на разобранный
+/// текст, а прочитанные из файла владеют своими словами (`Rows<'static>`).
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    file: Arc<str>,
+    rows: Vec<Row<'a>>,
+    /// позиция конца файла: (строка, колонка)
+    eof: (usize, usize),
+    encoding: Encoding,
+    /// ограничения, с которыми прочитан файл
+    limits: Limits,
+    /// заголовок файла, если сигнатура уже проверена и отброшена
+    header: Option<PrmHeader>,
+    /// ошибки лексического разбора и места недопустимых в кодировке байтов
+    lex_errors: Vec<(Span, LexError)>,
+}
+
+impl<'a> Rows<'a> {
+    /// Разбивает на слова текст PRM-файла `file`, не копируя его,
+    /// с ограничениями по умолчанию.
+    ///
+    /// Сигнатура остаётся первой строкой; её проверяет
+    /// [`PrmFile::check_signature`].
+    pub fn tokenize(file: &str, text: &'a str) -> Result<Self, PrmParseError> {
+        Self::tokenize_limited(file, text, Limits::default())
+    }
+
+    /// Как [`Rows::tokenize`], но с ограничениями `limits`
+    pub fn tokenize_limited(
+        file: &str,
+        text: &'a str,
+        limits: Limits,
+    ) -> Result<Self, PrmParseError> {
+        if text.len() > limits.max_file_size {
+            return Err(PrmParseError {
+                span: Span::file(file),
+                kind: LimitExceeded::FileSize(limits.max_file_size).into(),
+            });
+        }
+
+        let file: Arc<str> = Arc::from(file);
+        let mut lexer = Lexer::default();
+        let mut rows = vec![];
+        let mut eof = (1, 1);
+
+        for (index, (content, ending)) in lexer::split_lines(text).enumerate() {
+            let limit_error = |column, kind: LimitExceeded| PrmParseError {
+                span: Span {
+                    file: file.to_string(),
+                    line: index + 1,
+                    column,
+                    token: String::new(),
+                },
+                kind: kind.into(),
+            };
+
+            if content.len() > limits.max_line_length {
+                return Err(limit_error(
+                    1,
+                    LimitExceeded::LineLength(limits.max_line_length),
+                ));
+            }
+
+            let tokens = tokens_of(lexer.lex_line(content));
+            if !tokens.is_empty() {
+                if rows.len() == limits.max_rows {
+                    let column = tokens[0].column;
+                    return Err(limit_error(column, LimitExceeded::Rows(limits.max_rows)));
+                }
+                rows.push(Row {
+                    file: file.clone(),
+                    line: index + 1,
+                    tokens,
+                });
+            }
+            eof = if ending.is_empty() {
+                (index + 1, content.chars().count() + 1)
+            } else {
+                (index + 2, 1)
+            };
+        }
+
+        let lex_errors = lexer
+            .finish()
+            .into_iter()
+            .map(|(span, e)| {
+                let span = Span {
+                    file: file.to_string(),
+                    ..span
+                };
+                (span, e)
+            })
+            .collect();
+
+        Ok(Self {
+            file,
+            rows,
+            eof,
+            encoding: Encoding::Utf8,
+            limits,
+            header: None,
+            lex_errors,
+        })
+    }
+
+    /// Копия строк, не ссылающаяся на исходный текст
+    pub fn into_owned(self) -> Rows<'static> {
+        Rows {
+            file: self.file,
+            rows: self.rows.into_iter().map(Row::into_owned).collect(),
+            eof: self.eof,
+            encoding: self.encoding,
+            limits: self.limits,
+            header: self.header,
+            lex_errors: self.lex_errors,
+        }
+    }
+
+    /// Имя файла, из которого прочитаны строки
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Кодировка, в которой был прочитан файл
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Ограничения, которые должны соблюдать разборщики таблиц
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Заголовок файла; `None`, пока сигнатура не проверена
+    /// [`PrmFile::check_signature`]
+    pub fn header(&self) -> Option<&PrmHeader> {
+        self.header.as_ref()
+    }
+
+    /// Ошибки лексического разбора (незакрытые комментарии и кавычки)
+    /// и перекодирования, по одной на каждую строку с байтами, недопустимыми
+    /// в кодировке файла; в порядке строк
+    pub fn lex_errors(&self) -> impl Iterator<Item = PrmParseError> + '_ {
+        self.lex_errors.iter().map(|(span, e)| PrmParseError {
+            span: span.clone(),
+            kind: (*e).into(),
+        })
+    }
+
+    pub fn iter(&self) -> RowIter<'_> {
+        RowIter {
+            rows: self,
+            inner: self.rows.iter(),
+        }
+    }
+
+    /// Ошибка, возникшая из-за преждевременного конца файла
+    pub fn eof_error<E: Into<PrmErrorKind>>(&self, kind: E) -> PrmParseError {
+        PrmParseError {
+            span: Span {
+                file: self.file.to_string(),
+                line: self.eof.0,
+                column: self.eof.1,
+                token: String::new(),
+            },
+            kind: kind.into(),
         }
     }
+}
+
+impl<'a> std::ops::Deref for Rows<'a> {
+    type Target = [Row<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.rows
+    }
+}
+
+/// Итератор по строкам PRM-файла
+#[derive(Debug, Clone)]
+pub struct RowIter<'a> {
+    rows: &'a Rows<'a>,
+    inner: std::slice::Iter<'a, Row<'a>>,
+}
+
+impl<'a> RowIter<'a> {
+    /// Следующая строка либо ошибка `kind`, указывающая на конец файла
+    pub fn next_or<E: Into<PrmErrorKind>>(
+        &mut self,
+        kind: E,
+    ) -> Result<&'a Row<'a>, PrmParseError> {
+        self.inner.next().ok_or_else(|| self.rows.eof_error(kind))
+    }
+
+    /// Следующая строка без продвижения итератора
+    pub fn peek(&self) -> Option<&'a Row<'a>> {
+        self.inner.clone().next()
+    }
+
+    /// Ограничения, с которыми прочитан файл
+    pub fn limits(&self) -> &'a Limits {
+        &self.rows.limits
+    }
+}
+
+impl<'a> Iterator for RowIter<'a> {
+    type Item = &'a Row<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for RowIter<'_> {}
+
+/// Читает содержимое файла `file`, но не больше `limits.max_file_size` байт
+pub(crate) fn read_limited<R: Read>(
+    file: &str,
+    fin: R,
+    limits: &Limits,
+) -> Result<Vec<u8>, PrmParseError> {
+    let max_len = limits.max_file_size.saturating_add(1);
+    let mut bytes = vec![];
+    fin.take(max_len as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| PrmParseError {
+            span: Span::file(file),
+            kind: PrmOpenError::IO(e).into(),
+        })?;
+    if bytes.len() > limits.max_file_size {
+        return Err(PrmParseError {
+            span: Span::file(file),
+            kind: LimitExceeded::FileSize(limits.max_file_size).into(),
+        });
+    }
+    Ok(bytes)
+}
+
+/// Читает PRM-файл `file`, удаляя комментарии `//` и `/* */` и пустые строки.
+///
+/// `encoding` — кодировка файла, `None` — определить автоматически.
+/// Колонки слов совпадают с исходным текстом. Из `fin` читается
+/// не больше `limits.max_file_size` байт.
+pub fn read_without_comments<R: Read>(
+    file: &str,
+    fin: R,
+    encoding: Option<Encoding>,
+    limits: Limits,
+) -> Result<Rows<'static>, PrmParseError> {
+    let bytes = read_limited(file, fin, &limits)?;
+    let decoded = encoding::decode(file, &bytes, encoding);
+    let mut rows = Rows::tokenize_limited(file, &decoded.text, limits)?.into_owned();
+    rows.encoding = decoded.encoding;
 
-    lines
+    let error = LexError::InvalidEncoding(decoded.encoding);
+    rows.lex_errors
+        .extend(decoded.invalid.into_iter().map(|span| (span, error)));
+    rows.lex_errors.sort_by_key(|(span, _)| span.line);
+
+    Ok(rows)
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum PrmOpenError {
     #[error("can't open a file to read: `{0}`")]
     IO(#[from] std::io::Error),
-    #[error("wrong signature")]
-    WrongSignature,
+    #[error("wrong signature `{0}`, expected `{PRM_FAMILY}_Ver_<N>`")]
+    WrongSignature(String),
+    #[error("missing signature, expected `{PRM_FAMILY}_Ver_<N>`")]
+    MissingSignature,
+    #[error("unsupported file version `{0}`")]
+    UnsupportedVersion(PrmHeader),
 }
 
 #[derive(Debug, thiserror::Error)]
-pub enum PrmParseError {
+pub enum PrmErrorKind {
     #[error("parse error: {0}")]
     OpenFile(#[from] PrmOpenError),
+    #[error("lexical error: {0}")]
+    Lex(#[from] LexError),
+    #[error("limit exceeded: {0}")]
+    LimitExceeded(#[from] LimitExceeded),
     #[error("mechos parse error: {0}")]
     Mechos(#[from] MechosParseError),
     #[error("bunch parse error: {0}")]
@@ -102,10 +607,8 @@ pub enum PrmParseError {
     World(#[from] WorldParseError),
     #[error("price parse error: {0}")]
     Price(#[from] PriceParseError),
-    #[error("escave parse error: {0}")]
-    Escave(#[from] EscaveParseError),
-    #[error("spot parse error: {0}")]
-    Spot(#[from] SpotParseError),
+    #[error("location parse error: {0}")]
+    Location(#[from] LocationParseError),
     #[error("passage parse error: {0}")]
     Passage(#[from] PassageParseError),
     #[error("vangers-weight parse error: {0}")]
@@ -114,26 +617,274 @@ pub enum PrmParseError {
     Tabutask(#[from] TabutaskParseError),
 }
 
-pub trait PrmFile
-where
-    Self: Sized,
-{
-    fn file_name<'a>() -> &'a str;
+/// Ошибка разбора PRM-файла вместе с местом, где она произошла
+#[derive(Debug, thiserror::Error)]
+pub struct PrmParseError {
+    pub span: Span,
+    pub kind: PrmErrorKind,
+}
+
+impl std::fmt::Display for PrmParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)?;
+        if !self.span.token.is_empty() {
+            write!(f, " (at `{}`)", self.span.token)?;
+        }
+        Ok(())
+    }
+}
 
-    fn file_open<P: AsRef<Path>>(path_to_file: P) -> Result<Vec<String>, PrmOpenError> {
-        let file = std::fs::File::open(path_to_file.as_ref().join(Self::file_name()))
-            .map_err(|e| PrmOpenError::IO(e))?;
-        let fin = BufReader::new(file);
-        let mut rows = read_without_comments(fin);
+/// Результат разбора таблицы в режиме восстановления после ошибок
+#[derive(Debug)]
+pub struct Recovered<T> {
+    /// Всё, что удалось разобрать
+    pub table: T,
+    /// Найденные ошибки в порядке их появления в файле
+    pub diagnostics: Vec<PrmParseError>,
+}
 
-        if rows.len() == 0 || rows[0] != "uniVang-ParametersFile_Ver_1" {
-            return Err(PrmOpenError::WrongSignature);
+impl<T> Recovered<T> {
+    /// Ошибок не найдено
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Таблица, если ошибок нет, иначе первая из ошибок
+    pub fn into_result(self) -> Result<T, PrmParseError> {
+        match self.diagnostics.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(self.table),
         }
+    }
+}
+
+/// Сигнатура, с которой начинаются PRM-файлы оригинальной игры
+pub const PRM_SIGNATURE: &str = "uniVang-ParametersFile_Ver_1";
+
+/// Разбор таблицы из строк файла.
+///
+/// Таблицы параметризованы типом строковых значений: `TableWorld`
+/// (то же, что `TableWorld<String>`) владеет своими строками,
+/// а `TableWorld<&str>` ссылается на слова [`Rows`] и разбирается
+/// без копирования:
+///
+/// ```no_run
+/// # use vangers_prm::*;
+/// # fn main() -> Result<(), PrmParseError> {
+/// let text = std::fs::read_to_string("worlds.prm").unwrap();
+/// let rows = TableWorld::check_signature(Rows::tokenize("worlds.prm", &text)?)?;
+/// let worlds = TableWorld::<&str>::parse_rows(&rows)?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait PrmTable<'a>: Sized {
+    /// Разбирает строки файла, следующие за сигнатурой, не останавливаясь
+    /// на ошибках: неверные строки (или блоки) пропускаются, а ошибки
+    /// собираются в [`Recovered::diagnostics`]
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self>;
 
-        Vec::remove(&mut rows, 0);
+    /// Разбирает строки файла, следующие за сигнатурой
+    fn parse_rows(rows: &'a Rows<'_>) -> Result<Self, PrmParseError> {
+        recover(rows).into_result()
+    }
+}
+
+/// PRM-файл игры, разбираемый в таблицу, владеющую своими строками
+pub trait PrmFile: for<'a> PrmTable<'a> {
+    fn file_name<'a>() -> &'a str;
+
+    /// Понимает ли таблица файлы с заголовком `header`.
+    ///
+    /// По умолчанию принимается только `uniVang-ParametersFile_Ver_1`.
+    /// Таблица, знающая другие версии формата, переопределяет этот метод
+    /// и выбирает разбор по [`Rows::header`] в
+    /// [`PrmTable::parse_rows_recovering`].
+    fn supports_header(header: &PrmHeader) -> bool {
+        *header == PrmHeader::default()
+    }
+
+    /// Читает строки PRM-файла из `fin` и проверяет сигнатуру.
+    /// Кодировка определяется автоматически.
+    fn read_rows<R: BufRead>(fin: R) -> Result<Rows<'static>, PrmParseError> {
+        Self::read_rows_encoded(fin, None)
+    }
+
+    /// Читает строки PRM-файла в кодировке `encoding` (`None` — определить
+    /// автоматически) и проверяет сигнатуру
+    fn read_rows_encoded<R: Read>(
+        fin: R,
+        encoding: Option<Encoding>,
+    ) -> Result<Rows<'static>, PrmParseError> {
+        Self::read_rows_limited(fin, encoding, Limits::default())
+    }
+
+    /// Читает строки PRM-файла с ограничениями `limits` и проверяет
+    /// сигнатуру; разборщики таблиц соблюдают [`Rows::limits`]
+    fn read_rows_limited<R: Read>(
+        fin: R,
+        encoding: Option<Encoding>,
+        limits: Limits,
+    ) -> Result<Rows<'static>, PrmParseError> {
+        Self::check_signature(read_without_comments(
+            Self::file_name(),
+            fin,
+            encoding,
+            limits,
+        )?)
+    }
+
+    /// Проверяет сигнатуру в первой строке и отбрасывает её,
+    /// запоминая заголовок в [`Rows::header`]
+    fn check_signature(mut rows: Rows<'_>) -> Result<Rows<'_>, PrmParseError> {
+        let row = match rows.first() {
+            Some(row) => row,
+            None => return Err(rows.eof_error(PrmOpenError::MissingSignature)),
+        };
+
+        let header = match row.tokens() {
+            [token] => PrmHeader::parse(token.value()),
+            _ => None,
+        };
+        let header = match header {
+            Some(header) if Self::supports_header(&header) => header,
+            Some(header) => return Err(row.error(PrmOpenError::UnsupportedVersion(header))),
+            None => return Err(row.error(PrmOpenError::WrongSignature(row.to_string()))),
+        };
+
+        rows.rows.remove(0);
+        rows.header = Some(header);
 
         Ok(rows)
     }
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError>;
+    /// Открывает файл `file_name()` из источника ресурсов `source`
+    fn source_open<S: ResourceSource + ?Sized>(source: &S) -> Result<Rows<'static>, PrmParseError> {
+        let fin = source.open(Self::file_name()).map_err(|e| PrmParseError {
+            span: Span::file(Self::file_name()),
+            kind: PrmOpenError::IO(e).into(),
+        })?;
+
+        Self::read_rows(fin)
+    }
+
+    fn file_open<P: AsRef<Path>>(path_to_folder: P) -> Result<Rows<'static>, PrmParseError> {
+        Self::source_open(&DirSource::new(path_to_folder))
+    }
+
+    /// Разбирает файл `file_name()` из папки `path_to_folder`
+    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
+        Self::parse_rows(&Self::file_open(path_to_folder)?)
+    }
+
+    /// Разбирает файл `file_name()` из источника ресурсов `source`
+    fn load<S: ResourceSource + ?Sized>(source: &S) -> Result<Self, PrmParseError> {
+        Self::parse_rows(&Self::source_open(source)?)
+    }
+
+    /// Как [`PrmFile::load`], но с ограничениями `limits`
+    /// (например, для файлов, присланных пользователями)
+    fn load_limited<S: ResourceSource + ?Sized>(
+        source: &S,
+        limits: Limits,
+    ) -> Result<Self, PrmParseError> {
+        let fin = source.open(Self::file_name()).map_err(|e| PrmParseError {
+            span: Span::file(Self::file_name()),
+            kind: PrmOpenError::IO(e).into(),
+        })?;
+
+        Self::parse_rows(&Self::read_rows_limited(fin, None, limits)?)
+    }
+
+    /// Разбирает PRM-файл из произвольного источника
+    fn parse_reader<R: BufRead>(fin: R) -> Result<Self, PrmParseError> {
+        Self::parse_rows(&Self::read_rows(fin)?)
+    }
+
+    /// Разбирает PRM-файл из строки
+    fn parse_str(text: &str) -> Result<Self, PrmParseError> {
+        Self::parse_reader(text.as_bytes())
+    }
+
+    /// Разбирает PRM-файл из массива байт
+    fn parse_bytes(bytes: &[u8]) -> Result<Self, PrmParseError> {
+        Self::parse_reader(bytes)
+    }
+
+    /// Разбирает PRM-файл в кодировке `encoding` из произвольного источника
+    fn parse_reader_encoded<R: Read>(fin: R, encoding: Encoding) -> Result<Self, PrmParseError> {
+        Self::parse_rows(&Self::read_rows_encoded(fin, Some(encoding))?)
+    }
+
+    /// Разбирает файл `file_name()` в кодировке `encoding` из источника
+    /// ресурсов `source`
+    fn load_encoded<S: ResourceSource + ?Sized>(
+        source: &S,
+        encoding: Encoding,
+    ) -> Result<Self, PrmParseError> {
+        let fin = source.open(Self::file_name()).map_err(|e| PrmParseError {
+            span: Span::file(Self::file_name()),
+            kind: PrmOpenError::IO(e).into(),
+        })?;
+
+        Self::parse_reader_encoded(fin, encoding)
+    }
+
+    /// Разбирает таблицу из документа, сохраняющего форматирование файла
+    fn parse_document(document: &PrmDocument) -> Result<Self, PrmParseError> {
+        Self::parse_rows(&Self::check_signature(document.rows(Self::file_name()))?)
+    }
+
+    /// Как [`PrmFile::file_parse`], но с восстановлением после ошибок.
+    ///
+    /// `Err` возвращается только если файл не удалось открыть
+    /// или у него неверная сигнатура.
+    fn file_parse_recovering<P: AsRef<Path>>(
+        path_to_folder: P,
+    ) -> Result<Recovered<Self>, PrmParseError> {
+        Ok(recover(&Self::file_open(path_to_folder)?))
+    }
+
+    /// Как [`PrmFile::load`], но с восстановлением после ошибок
+    fn load_recovering<S: ResourceSource + ?Sized>(
+        source: &S,
+    ) -> Result<Recovered<Self>, PrmParseError> {
+        Ok(recover(&Self::source_open(source)?))
+    }
+
+    /// Как [`PrmFile::parse_reader`], но с восстановлением после ошибок
+    fn parse_reader_recovering<R: BufRead>(fin: R) -> Result<Recovered<Self>, PrmParseError> {
+        Ok(recover(&Self::read_rows(fin)?))
+    }
+
+    /// Как [`PrmFile::parse_str`], но с восстановлением после ошибок
+    fn parse_str_recovering(text: &str) -> Result<Recovered<Self>, PrmParseError> {
+        Self::parse_reader_recovering(text.as_bytes())
+    }
+}
+
+/// Разбор с восстановлением, дополненный ошибками лексического разбора
+/// и перекодирования строк
+fn recover<'a, T: PrmTable<'a>>(rows: &'a Rows<'_>) -> Recovered<T> {
+    let Recovered {
+        table,
+        diagnostics: parsed,
+    } = T::parse_rows_recovering(rows);
+
+    let mut diagnostics = Vec::with_capacity(parsed.len() + rows.lex_errors.len());
+    let mut lex_errors = rows.lex_errors().peekable();
+    for e in parsed {
+        while let Some(lex_error) = lex_errors.next_if(|x| x.span.line <= e.span.line) {
+            diagnostics.push(lex_error);
+        }
+        diagnostics.push(e);
+    }
+    diagnostics.extend(lex_errors);
+
+    Recovered { table, diagnostics }
+}
+
+/// Представление таблицы в виде строк PRM-файла (без сигнатуры)
+pub trait ToPrmRows {
+    /// Слова каждой строки в том порядке, в котором их ожидает разборщик
+    fn to_prmrows(&self) -> Vec<Vec<String>>;
 }
@@ -0,0 +1,52 @@
+/// Ограничения на размер разбираемых данных.
+///
+/// Защищают от огромных выделений памяти на присланных пользователями
+/// файлах: счётчики из файла не используются как размер буферов, пока
+/// не проверены этими ограничениями.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Наибольший размер файла в байтах
+    pub max_file_size: usize,
+    /// Наибольшая длина строки файла в байтах
+    pub max_line_length: usize,
+    /// Наибольшее число строк с данными
+    pub max_rows: usize,
+    /// Наибольшее число циклов в банче
+    pub max_cycles: usize,
+}
+
+/// Ограничения с большим запасом для файлов оригинальной игры и модов
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 16 * 1024 * 1024,
+            max_line_length: 64 * 1024,
+            max_rows: 100_000,
+            max_cycles: 256,
+        }
+    }
+}
+
+impl Limits {
+    /// Без ограничений (для доверенных файлов)
+    pub fn unlimited() -> Self {
+        Self {
+            max_file_size: usize::MAX,
+            max_line_length: usize::MAX,
+            max_rows: usize::MAX,
+            max_cycles: usize::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum LimitExceeded {
+    #[error("file is larger than {0} bytes")]
+    FileSize(usize),
+    #[error("line is longer than {0} bytes")]
+    LineLength(usize),
+    #[error("file has more than {0} rows")]
+    Rows(usize),
+    #[error("bunch has {found} cycles, more than {limit}")]
+    Cycles { limit: usize, found: usize },
+}
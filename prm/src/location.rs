@@ -0,0 +1,260 @@
+use crate::{lexer::quote, PrmParseError, Recovered, Row, RowIter, Rows, TableEscave, TableSpot};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocationParseError {
+    #[error("expected first line of the block not found")]
+    ExpectedTitleLine,
+    #[error("unexpected additional parameter at first line of the block")]
+    UnexpectedAdditionalParameterAtTitleLine,
+    #[error("`name` property")]
+    Name,
+    #[error("`world` property")]
+    World,
+    #[error("`pos_x` property")]
+    PosX,
+    #[error("`pos_y` property")]
+    PosY,
+    #[error("`personal_item` property")]
+    PersonalItem,
+    #[error("expected `none` terminate line not found")]
+    ExpectedTerminateLine,
+    #[error("`goods` line")]
+    GoodsLine,
+    #[error("unexpected additional parameter at goods line")]
+    UnexpectedAdditionalParameterAtGoodsLine,
+    #[error("first columns differ from the previous rows of the block")]
+    TitleMismatch,
+    #[error("rows of the block are separated by another block")]
+    SplitBlock,
+}
+
+/// Вид торговой точки: в каком файле она описана
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LocationKind {
+    /// Эскейв из `escaves.prm`
+    Escave,
+    /// Спот из `spot.prm`
+    Spot,
+}
+
+impl LocationKind {
+    pub const NAMES: &'static [&'static str] = &["escave", "spot"];
+
+    /// Название вида: `escave` или `spot`
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+
+/// Торговая точка: эскейв или спот
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Location<S = String> {
+    /// Эскейв или спот
+    pub kind: LocationKind,
+    /// Название точки
+    pub name: S,
+    /// Название мира, в котором расположена точка
+    pub world_name: S,
+    /// Абсцисса точки
+    pub pos_x: i32,
+    /// Ордината точки
+    pub pos_y: i32,
+    /// Личная вещь советника этой точки (если есть)
+    pub personal_item_name: Option<S>,
+    /// Список производимых продуктов в точке и места их назначения
+    pub goods: Vec<(S, S)>,
+}
+
+impl<'a, S: From<&'a str>> Location<S> {
+    /// Разбирает блоки точек вида `kind` до конца файла; блок с ошибкой
+    /// пропускается целиком
+    pub(crate) fn parse_blocks(rows: &'a Rows<'_>, kind: LocationKind) -> Recovered<Vec<Self>> {
+        let mut locations = vec![];
+        let mut diagnostics = vec![];
+
+        let mut iter = rows.iter();
+        while iter.peek().is_some() {
+            match Self::from_prmrow_iter(&mut iter, kind) {
+                Ok(location) => locations.push(location),
+                Err(e) => {
+                    diagnostics.push(e);
+                    // пропускаем остаток блока: до заголовка следующего
+                    // или до завершающей строки `none` включительно
+                    while let Some(row) = iter.peek() {
+                        if Self::is_title(row) {
+                            break;
+                        }
+                        iter.next();
+                        if row.is("none") {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        Recovered {
+            table: locations,
+            diagnostics,
+        }
+    }
+
+    fn from_prmrow_iter(iter: &mut RowIter<'a>, kind: LocationKind) -> Result<Self, PrmParseError> {
+        use LocationParseError::*;
+
+        let row = iter.next_or(ExpectedTitleLine)?;
+        let mut location = Self::from_title_prmrow(row, kind)?;
+
+        // строки товаров `<товар> <место назначения>` до строки `none`
+        loop {
+            let row = iter.clone().next_or(ExpectedTerminateLine)?;
+            if Self::is_title(row) {
+                // начался следующий блок, а `none` так и не встретилась
+                return Err(row.error(ExpectedTerminateLine));
+            }
+
+            iter.next();
+            if row.is("none") {
+                return Ok(location);
+            }
+            location.goods.push(Self::goods_from_prmrow(row)?);
+        }
+    }
+
+    /// Разбирает первую строку блока; товары остаются пустыми
+    pub(crate) fn from_title_prmrow(
+        row: &'a Row<'a>,
+        kind: LocationKind,
+    ) -> Result<Self, PrmParseError> {
+        use LocationParseError::*;
+
+        let mut iter = row.fields();
+
+        let name = iter.word(Name)?.into();
+        let world_name = iter.word(World)?.into();
+        let pos_x = iter.parse(PosX)?;
+        let pos_y = iter.parse(PosY)?;
+        let personal_item_name =
+            iter.next()
+                .and_then(|s| if s == "none" { None } else { Some(s.into()) });
+
+        iter.finish(UnexpectedAdditionalParameterAtTitleLine)?;
+
+        Ok(Self {
+            kind,
+            name,
+            world_name,
+            pos_x,
+            pos_y,
+            personal_item_name,
+            goods: vec![],
+        })
+    }
+
+    /// Разбирает строку товара: `<товар> <место назначения>`
+    pub(crate) fn goods_from_prmrow(row: &'a Row<'a>) -> Result<(S, S), PrmParseError> {
+        use LocationParseError::*;
+
+        let mut iter = row.fields();
+        let item = iter.word(GoodsLine)?.into();
+        let dest = iter.word(GoodsLine)?.into();
+
+        iter.finish(UnexpectedAdditionalParameterAtGoodsLine)?;
+
+        Ok((item, dest))
+    }
+
+    /// Похожа ли строка на первую строку блока
+    fn is_title(row: &Row<'_>) -> bool {
+        let tokens = row.tokens();
+        (tokens.len() == 4 || tokens.len() == 5)
+            && tokens[2].text.parse::<i32>().is_ok()
+            && tokens[3].text.parse::<i32>().is_ok()
+    }
+}
+
+impl<S: AsRef<str>> Location<S> {
+    pub(crate) fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let title = vec![
+            quote(self.name.as_ref()),
+            quote(self.world_name.as_ref()),
+            self.pos_x.to_string(),
+            self.pos_y.to_string(),
+            quote(
+                self.personal_item_name
+                    .as_ref()
+                    .map_or("none", AsRef::as_ref),
+            ),
+        ];
+        let goods = self
+            .goods
+            .iter()
+            .map(|(item, dest)| vec![quote(item.as_ref()), quote(dest.as_ref())]);
+
+        // список товаров завершается строкой `none`
+        let none = vec![String::from("none")];
+
+        std::iter::once(title)
+            .chain(goods)
+            .chain(std::iter::once(none))
+            .collect()
+    }
+
+    /// Та же точка со ссылками на строки этой
+    pub fn as_borrowed(&self) -> Location<&str> {
+        Location {
+            kind: self.kind,
+            name: self.name.as_ref(),
+            world_name: self.world_name.as_ref(),
+            pos_x: self.pos_x,
+            pos_y: self.pos_y,
+            personal_item_name: self.personal_item_name.as_ref().map(AsRef::as_ref),
+            goods: self
+                .goods
+                .iter()
+                .map(|(item, dest)| (item.as_ref(), dest.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+/// Все торговые точки: эскейвы из `escaves.prm`, затем споты из `spot.prm`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TableLocations<S = String> {
+    pub locations: Vec<Location<S>>,
+}
+
+impl<S> TableLocations<S> {
+    pub fn new(escaves: TableEscave<S>, spots: TableSpot<S>) -> Self {
+        let mut locations = escaves.escaves;
+        locations.extend(spots.spots);
+        Self { locations }
+    }
+
+    /// Точки вида `kind`
+    pub fn of_kind(&self, kind: LocationKind) -> impl Iterator<Item = &Location<S>> {
+        self.locations
+            .iter()
+            .filter(move |location| location.kind == kind)
+    }
+}
+
+impl<S: AsRef<str>> TableLocations<S> {
+    /// Точка с названием `name`, в каком бы файле она ни была описана
+    pub fn get(&self, name: &str) -> Option<&Location<S>> {
+        self.locations
+            .iter()
+            .find(|location| location.name.as_ref() == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+}
@@ -1,11 +1,15 @@
-use std::{path::Path, str::FromStr};
-
 use ::enum_primitive_derive::Primitive;
 use ::num_traits::FromPrimitive;
 
-use crate::{PrmFile, PrmParseError};
+use crate::{
+    lexer::quote, PrmErrorKind, PrmFile, PrmParseError, PrmTable, Recovered, Row, Rows, ToPrmRows,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Type {
     Raffa = 0,
     Light = 1,
@@ -15,19 +19,90 @@ pub enum Type {
     Special = 5,
 }
 
-pub struct Price {
+impl Type {
+    /// Названия типов мехосов по порядку их номеров
+    pub const NAMES: &'static [&'static str] =
+        &["Raffa", "Light", "Microbus", "Atw", "Track", "Special"];
+}
+
+/// Группа счётчиков `car.prm`: мехосы записаны в файле подряд по группам,
+/// сначала основные, затем раффы, затем мехосы конструктора
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MechosGroup {
+    Main = 0,
+    Raffa = 1,
+    Constructor = 2,
+}
+
+impl MechosGroup {
+    /// Названия групп по порядку их номеров
+    pub const NAMES: &'static [&'static str] = &["Main", "Raffa", "Constructor"];
+
+    /// Все группы в порядке их записи в файл
+    pub const ALL: [MechosGroup; 3] = [Self::Main, Self::Raffa, Self::Constructor];
+}
+
+/// Счётчики из первых трёх строк `car.prm`: число мехосов в каждой группе
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct MechosCounters {
+    pub main: usize,
+    pub raffa: usize,
+    pub constructor: usize,
+}
+
+impl MechosCounters {
+    pub fn get(&self, group: MechosGroup) -> usize {
+        match group {
+            MechosGroup::Main => self.main,
+            MechosGroup::Raffa => self.raffa,
+            MechosGroup::Constructor => self.constructor,
+        }
+    }
+
+    pub fn get_mut(&mut self, group: MechosGroup) -> &mut usize {
+        match group {
+            MechosGroup::Main => &mut self.main,
+            MechosGroup::Raffa => &mut self.raffa,
+            MechosGroup::Constructor => &mut self.constructor,
+        }
+    }
+
+    /// Сколько всего мехосов должно быть в файле; `None`, если сумма
+    /// счётчиков не помещается в `usize`
+    pub fn total(&self) -> Option<usize> {
+        self.main
+            .checked_add(self.raffa)?
+            .checked_add(self.constructor)
+    }
+}
+
+/// Цена мехоса в магазине
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct MechosPrice {
     pub buy: u32,
     pub sell: u32,
 }
 
 /// Характеристики мехоса взятые из файла `car.prm`
-pub struct Mechos {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Mechos<S = String> {
     /// название мехоса
-    pub name: String,
+    pub name: S,
     /// тип мехоса,
     pub r#type: Type,
     /// цена мехоса
-    pub price: Price,
+    pub price: MechosPrice,
     /// вместимость (количество слотов для каждого вида)
     pub r#box: (u8, u8, u8, u8),
     pub speed: u32,
@@ -42,13 +117,18 @@ pub struct Mechos {
     pub fly: u32,
     pub damage: u32,
     pub teleport: u32,
-    __: (),
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum MechosParseError {
     #[error("block `total item number in Chain at one moment` parse error")]
     DigitCounters,
+    #[error("counter group of the mechos")]
+    CounterGroup,
+    #[error("counters declare {declared} mechoses, but the file has {found}")]
+    CountersMismatch { declared: usize, found: usize },
+    #[error("counters sum up to more than {}", usize::MAX)]
+    CountersOverflow,
     #[error("field parse error: {0}")]
     FieldParseError(#[from] MechosFieldParseError),
 }
@@ -93,102 +173,286 @@ pub enum MechosFieldParseError {
     Teleport,
 }
 
-impl From<MechosFieldParseError> for PrmParseError {
+impl From<MechosFieldParseError> for PrmErrorKind {
     fn from(from: MechosFieldParseError) -> Self {
         Self::Mechos(MechosParseError::FieldParseError(from))
     }
 }
 
 /// Таблица с характеристиками всех мехосов из файла `car.prm`
-pub struct TableMechos {
-    mechoses: Vec<Mechos>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct TableMechos<S = String> {
+    /// счётчики из первых трёх строк файла
+    pub(crate) counters: MechosCounters,
+    pub(crate) mechoses: Vec<Mechos<S>>,
 }
 
-impl TableMechos {
-    pub fn mechoses(&self) -> &[Mechos] {
+impl<S> TableMechos<S> {
+    /// Пустая таблица; мехосы добавляются [`TableMechos::push`]
+    pub fn new() -> Self {
+        Self {
+            counters: MechosCounters::default(),
+            mechoses: vec![],
+        }
+    }
+
+    pub fn mechoses(&self) -> &[Mechos<S>] {
         &self.mechoses
     }
+
+    pub fn mechoses_mut(&mut self) -> &mut [Mechos<S>] {
+        &mut self.mechoses
+    }
+
+    pub fn counters(&self) -> MechosCounters {
+        self.counters
+    }
+
+    /// Группа счётчиков для каждого мехоса по порядку; мехосы сверх суммы
+    /// счётчиков (только в таблице, разобранной с ошибками) относятся
+    /// к последней группе
+    pub fn groups(&self) -> impl Iterator<Item = MechosGroup> + '_ {
+        MechosGroup::ALL
+            .iter()
+            .flat_map(move |&group| std::iter::repeat_n(group, self.counters.get(group)))
+            .chain(std::iter::repeat(MechosGroup::Constructor))
+            .take(self.mechoses.len())
+    }
+
+    /// Мехосы группы `group`
+    pub fn mechoses_of(&self, group: MechosGroup) -> &[Mechos<S>] {
+        let start = MechosGroup::ALL
+            .iter()
+            .take_while(|&&g| g != group)
+            .map(|&g| self.counters.get(g))
+            .fold(0, usize::saturating_add)
+            .min(self.mechoses.len());
+        let end = start
+            .saturating_add(self.counters.get(group))
+            .min(self.mechoses.len());
+        &self.mechoses[start..end]
+    }
+
+    /// Добавляет мехос в конец группы `group`; счётчик группы
+    /// увеличивается. Если сумма счётчиков перестаёт помещаться в `usize`,
+    /// мехос не добавляется: ошибка [`MechosParseError::CountersOverflow`]
+    pub fn push(&mut self, group: MechosGroup, mechos: Mechos<S>) -> Result<(), MechosParseError> {
+        let mut counters = self.counters;
+        let counter = counters.get_mut(group);
+        *counter = counter
+            .checked_add(1)
+            .ok_or(MechosParseError::CountersOverflow)?;
+        counters.total().ok_or(MechosParseError::CountersOverflow)?;
+
+        let end = MechosGroup::ALL
+            .iter()
+            .take_while(|&&g| g as u8 <= group as u8)
+            .map(|&g| self.counters.get(g))
+            .fold(0, usize::saturating_add)
+            .min(self.mechoses.len());
+        self.mechoses.insert(end, mechos);
+        self.counters = counters;
+        Ok(())
+    }
+
+    /// Удаляет мехос с номером `index`; счётчик его группы уменьшается
+    pub fn remove(&mut self, index: usize) -> Mechos<S> {
+        let group = self.groups().nth(index);
+        if let Some(group) = group {
+            let counter = self.counters.get_mut(group);
+            *counter = counter.saturating_sub(1);
+        }
+        self.mechoses.remove(index)
+    }
+}
+
+impl<S> Default for TableMechos<S> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PrmFile for TableMechos {
     fn file_name<'a>() -> &'a str {
         "car.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        use MechosFieldParseError::*;
+impl<'a, S: From<&'a str>> PrmTable<'a> for TableMechos<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
+        let mut diagnostics = vec![];
 
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
+        let mut counters = MechosCounters::default();
+        let mut counted = true;
+        let mut overflowed = false;
+        let mut iter = rows.iter();
+        for group in MechosGroup::ALL {
+            let parsed = iter
+                .next_or(MechosParseError::DigitCounters)
+                .and_then(|row| {
+                    let mut values = row.fields();
+                    let counter = values.parse(MechosParseError::DigitCounters)?;
+                    values.finish(MechosParseError::DigitCounters)?;
 
-        let mechoses_total = rows
-            .iter()
-            .take(3)
-            .map(|r| r.parse::<usize>())
-            .try_fold(0, |acc, x| x.map(|x| acc + x))
-            .map_err(|_| MechosParseError::DigitCounters)?;
-
-        let mut mechoses = Vec::with_capacity(mechoses_total);
-
-        for row in rows.iter().skip(3) {
-            let mut values = row.split_whitespace();
-            let name = values.next().ok_or(Name)?;
-
-            let r#type = {
-                let type_id = values
-                    .next()
-                    .ok_or(Type(String::from("[expected a value]")))
-                    .map(|s| s.parse::<u8>().map_err(|e| Type(e.to_string())))??;
-
-                self::Type::from_u8(type_id).ok_or(WrongType(type_id))?
-            };
-
-            let price = Price {
-                buy: get_atom(&mut values).ok_or(PriceBuy)?,
-                sell: get_atom(&mut values).ok_or(PriceSell)?,
-            };
-
-            let boxes = {
-                let mut boxes = [0u8; 4];
-                for (i, b) in boxes.iter_mut().enumerate() {
-                    *b = values
-                        .next()
-                        .ok_or(Box(i))
-                        .map(|v| v.parse().map_err(|_| Box(i)))??;
+                    let mut next = counters;
+                    *next.get_mut(group) = counter;
+                    if next.total().is_none() {
+                        return Err(row.error(MechosParseError::CountersOverflow));
+                    }
+                    Ok(next)
+                });
+            match parsed {
+                Ok(next) => counters = next,
+                Err(e) => {
+                    overflowed |= matches!(
+                        e.kind,
+                        PrmErrorKind::Mechos(MechosParseError::CountersOverflow)
+                    );
+                    diagnostics.push(e);
+                    counted = false;
                 }
-                (boxes[0], boxes[1], boxes[2], boxes[3])
-            };
-
-            let mechos = Mechos {
-                name: name.to_owned(),
-                r#type,
-                price,
-                r#box: boxes,
-                speed: get_atom(&mut values).ok_or(Speed)?,
-                armor: get_atom(&mut values).ok_or(Armor)?,
-                energy: get_atom(&mut values).ok_or(Energy)?,
-                energy_delta: get_atom(&mut values).ok_or(EnergyDelta)?,
-                energy_drop: get_atom(&mut values).ok_or(EnergyDrop)?,
-                drop_time: get_atom(&mut values).ok_or(DropTime)?,
-                fire: get_atom(&mut values).ok_or(Fire)?,
-                water: get_atom(&mut values).ok_or(Water)?,
-                oxygen: get_atom(&mut values).ok_or(Oxygen)?,
-                fly: get_atom(&mut values).ok_or(Fly)?,
-                damage: get_atom(&mut values).ok_or(Damage)?,
-                teleport: get_atom(&mut values).ok_or(Teleport)?,
-                __: (),
-            };
-
-            mechoses.push(mechos);
+            }
+        }
+
+        // после переполнения счётчики ничего не значат: таблица остаётся
+        // без них, мехосы относятся к последней группе
+        if overflowed {
+            counters = MechosCounters::default();
+        }
+
+        // счётчики из файла не используются как размер буфера: их сумма
+        // может быть сколь угодно большой
+        let mechos_rows: Vec<_> = iter.collect();
+        let mut mechoses = Vec::with_capacity(mechos_rows.len());
+
+        // сумма разобранных счётчиков проверена выше
+        let declared = counters.total().unwrap_or(usize::MAX);
+        if counted && declared != mechos_rows.len() {
+            // лишний мехос — ошибка на нём, недостающий — на первом счётчике
+            let row = mechos_rows
+                .get(declared)
+                .copied()
+                .or_else(|| rows.iter().next());
+            if let Some(row) = row {
+                diagnostics.push(row.error(MechosParseError::CountersMismatch {
+                    declared,
+                    found: mechos_rows.len(),
+                }));
+            }
+        }
+
+        for row in mechos_rows {
+            match Mechos::from_prmrow(row) {
+                Ok(mechos) => mechoses.push(mechos),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { counters, mechoses },
+            diagnostics,
         }
+    }
+}
+
+impl<'a, S: From<&'a str>> Mechos<S> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
+        use MechosFieldParseError::*;
+
+        let mut values = row.fields();
+        let name = values.word(Name)?;
+
+        let r#type = {
+            let type_id = values
+                .word(Type(String::from("[expected a value]")))
+                .and_then(|s| {
+                    s.parse::<u8>()
+                        .map_err(|e| values.error(Type(e.to_string())))
+                })?;
+
+            self::Type::from_u8(type_id).ok_or_else(|| values.error(WrongType(type_id)))?
+        };
+
+        let price = MechosPrice {
+            buy: values.parse(PriceBuy)?,
+            sell: values.parse(PriceSell)?,
+        };
+
+        let boxes = {
+            let mut boxes = [0u8; 4];
+            for (i, b) in boxes.iter_mut().enumerate() {
+                *b = values.parse(Box(i))?;
+            }
+            (boxes[0], boxes[1], boxes[2], boxes[3])
+        };
 
-        Ok(Self { mechoses })
+        let mechos = Mechos {
+            name: name.into(),
+            r#type,
+            price,
+            r#box: boxes,
+            speed: values.parse(Speed)?,
+            armor: values.parse(Armor)?,
+            energy: values.parse(Energy)?,
+            energy_delta: values.parse(EnergyDelta)?,
+            energy_drop: values.parse(EnergyDrop)?,
+            drop_time: values.parse(DropTime)?,
+            fire: values.parse(Fire)?,
+            water: values.parse(Water)?,
+            oxygen: values.parse(Oxygen)?,
+            fly: values.parse(Fly)?,
+            damage: values.parse(Damage)?,
+            teleport: values.parse(Teleport)?,
+        };
+
+        Ok(mechos)
     }
 }
 
-#[inline]
-fn get_atom<'a, 'b, I, T: FromStr>(iter: &'a mut I) -> Option<T>
-where
-    I: Iterator<Item = &'b str>,
-{
-    iter.next()?.parse::<T>().ok()
+impl<S: AsRef<str>> Mechos<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        let mut row = vec![
+            quote(self.name.as_ref()),
+            (self.r#type as u8).to_string(),
+            self.price.buy.to_string(),
+            self.price.sell.to_string(),
+            self.r#box.0.to_string(),
+            self.r#box.1.to_string(),
+            self.r#box.2.to_string(),
+            self.r#box.3.to_string(),
+        ];
+
+        row.extend(
+            [
+                self.speed,
+                self.armor,
+                self.energy,
+                self.energy_delta,
+                self.energy_drop,
+                self.drop_time,
+                self.fire,
+                self.water,
+                self.oxygen,
+                self.fly,
+                self.damage,
+                self.teleport,
+            ]
+            .iter()
+            .map(|v| v.to_string()),
+        );
+
+        row
+    }
+}
+
+impl<S: AsRef<str>> ToPrmRows for TableMechos<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let counters = MechosGroup::ALL
+            .iter()
+            .map(|&group| vec![self.counters.get(group).to_string()]);
+        let mechoses = self.mechoses.iter().map(Mechos::to_prmrow);
+        counters.chain(mechoses).collect()
+    }
 }
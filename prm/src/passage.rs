@@ -1,6 +1,4 @@
-use std::path::Path;
-
-use crate::{PrmFile, PrmParseError};
+use crate::{lexer::quote, PrmFile, PrmParseError, PrmTable, Recovered, Row, Rows, ToPrmRows};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PassageParseError {
@@ -18,34 +16,35 @@ pub enum PassageParseError {
     UnexpectedAdditionalParameter,
 }
 
-pub struct Passage {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Passage<S = String> {
     /// Название коридора
-    pub name: String,
+    pub name: S,
     /// Название мира, в котором расположен коридор
-    pub world_src_name: String,
+    pub world_src_name: S,
     /// Название мира, в который ведёт коридор
-    pub world_dest_name: String,
+    pub world_dest_name: S,
     /// Абсцисса коридора
     pub pos_x: i32,
     /// Ордината коридора
     pub pos_y: i32,
 }
 
-impl Passage {
-    fn from_prmrow(row: &str) -> Result<Self, PassageParseError> {
+impl<'a, S: From<&'a str>> Passage<S> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
         use PassageParseError::*;
 
-        let mut iter = row.split_whitespace();
+        let mut iter = row.fields();
 
-        let name = iter.next().ok_or(Name)?.to_owned();
-        let world_src_name = iter.next().ok_or(WorldSource)?.to_owned();
-        let world_dest_name = iter.next().ok_or(WorldDestination)?.to_owned();
-        let pos_x = iter.next().and_then(|x| x.parse().ok()).ok_or(PosX)?;
-        let pos_y = iter.next().and_then(|y| y.parse().ok()).ok_or(PosY)?;
+        let name = iter.word(Name)?.into();
+        let world_src_name = iter.word(WorldSource)?.into();
+        let world_dest_name = iter.word(WorldDestination)?.into();
+        let pos_x = iter.parse(PosX)?;
+        let pos_y = iter.parse(PosY)?;
 
-        if iter.next().is_some() {
-            Err(UnexpectedAdditionalParameter)?
-        }
+        iter.finish(UnexpectedAdditionalParameter)?;
 
         Ok(Self {
             name,
@@ -57,23 +56,89 @@ impl Passage {
     }
 }
 
-pub struct TableSpot {
-    pub passages: Vec<Passage>,
+impl<S: AsRef<str>> Passage<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            quote(self.name.as_ref()),
+            quote(self.world_src_name.as_ref()),
+            quote(self.world_dest_name.as_ref()),
+            self.pos_x.to_string(),
+            self.pos_y.to_string(),
+        ]
+    }
 }
 
-impl PrmFile for TableSpot {
+/// Таблица со всеми коридорами между мирами из `passages.prm`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct TablePassage<S = String> {
+    pub(crate) passages: Vec<Passage<S>>,
+}
+
+impl<S> TablePassage<S> {
+    pub fn new(passages: Vec<Passage<S>>) -> Self {
+        Self { passages }
+    }
+
+    pub fn passages(&self) -> &[Passage<S>] {
+        &self.passages
+    }
+
+    pub fn passages_mut(&mut self) -> &mut Vec<Passage<S>> {
+        &mut self.passages
+    }
+}
+
+impl<S: AsRef<str>> TablePassage<S> {
+    /// Коридор с названием `name`
+    pub fn get(&self, name: &str) -> Option<&Passage<S>> {
+        self.passages
+            .iter()
+            .find(|passage| passage.name.as_ref() == name)
+    }
+
+    /// Коридоры, ведущие из мира `world`
+    pub fn leaving<'t>(&'t self, world: &'t str) -> impl Iterator<Item = &'t Passage<S>> {
+        self.passages
+            .iter()
+            .filter(move |passage| passage.world_src_name.as_ref() == world)
+    }
+
+    /// Коридоры, ведущие в мир `world`
+    pub fn entering<'t>(&'t self, world: &'t str) -> impl Iterator<Item = &'t Passage<S>> {
+        self.passages
+            .iter()
+            .filter(move |passage| passage.world_dest_name.as_ref() == world)
+    }
+}
+
+impl PrmFile for TablePassage {
     fn file_name<'a>() -> &'a str {
         "passages.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
-
+impl<'a, S: From<&'a str>> PrmTable<'a> for TablePassage<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
         let mut passages = vec![];
+        let mut diagnostics = vec![];
         for row in rows.iter() {
-            passages.push(Passage::from_prmrow(row)?);
+            match Passage::from_prmrow(row) {
+                Ok(passage) => passages.push(passage),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+
+        Recovered {
+            table: Self { passages },
+            diagnostics,
         }
+    }
+}
 
-        Ok(Self { passages })
+impl<S: AsRef<str>> ToPrmRows for TablePassage<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        self.passages.iter().map(Passage::to_prmrow).collect()
     }
 }
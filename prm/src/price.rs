@@ -1,5 +1,7 @@
-use crate::{PrmFile, PrmParseError};
-use std::{collections::HashMap, path::Path};
+use crate::{lexer::quote, PrmFile, PrmParseError, PrmTable, Recovered, Row, Rows, ToPrmRows};
+use std::hash::Hash;
+
+use indexmap::IndexMap;
 
 #[derive(Debug, thiserror::Error)]
 pub enum PriceParseError {
@@ -13,75 +15,131 @@ pub enum PriceParseError {
     UnexpectedAdditionalParameter,
     #[error("title of a block that includes an escave name is not found")]
     ExpectedTitleBlock,
+    #[error("block of the same escave is already defined")]
+    DuplicateTitle,
 }
 
-pub struct Price {
-    pub name: String,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Price<S = String> {
+    pub name: S,
     pub buy: u32,
     pub sell: u32,
 }
 
-impl Price {
-    fn from_prmrow(row: &str) -> Result<Self, PriceParseError> {
-        let mut iter = row.split_whitespace();
-
-        let name = iter.next().ok_or(PriceParseError::Name)?.to_owned();
-        let buy = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(PriceParseError::Buy)?;
-        let sell = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(PriceParseError::Sell)?;
-
-        if iter.next().is_some() {
-            Err(PriceParseError::UnexpectedAdditionalParameter)?
-        }
+impl<'a, S: From<&'a str>> Price<S> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
+        let mut iter = row.fields();
+
+        let name = iter.word(PriceParseError::Name)?.into();
+        let buy = iter.parse(PriceParseError::Buy)?;
+        let sell = iter.parse(PriceParseError::Sell)?;
+
+        iter.finish(PriceParseError::UnexpectedAdditionalParameter)?;
 
         Ok(Price { name, buy, sell })
     }
 }
 
-pub struct TablePrice {
-    pub prices: HashMap<String, Vec<Price>>,
+impl<S: AsRef<str>> Price<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            quote(self.name.as_ref()),
+            self.buy.to_string(),
+            self.sell.to_string(),
+        ]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "S: serde::Deserialize<'de> + Eq + std::hash::Hash"))
+)]
+#[cfg_attr(
+    feature = "cache",
+    bincode(
+        encode_bounds = "S: serde::Serialize",
+        decode_bounds = "S: serde::de::DeserializeOwned + Eq + std::hash::Hash",
+        borrow_decode_bounds = "S: serde::de::DeserializeOwned + Eq + std::hash::Hash"
+    )
+)]
+pub struct TablePrice<S = String> {
+    /// Цены по эскейвам в порядке блоков файла
+    #[cfg_attr(feature = "cache", bincode(with_serde))]
+    pub prices: IndexMap<S, Vec<Price<S>>>,
 }
 
 impl PrmFile for TablePrice {
     fn file_name<'a>() -> &'a str {
         "price.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
-
-        let mut prices = HashMap::new();
+impl<'a, S: From<&'a str> + Eq + Hash> PrmTable<'a> for TablePrice<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
+        let mut prices = IndexMap::new();
+        let mut diagnostics = vec![];
         let mut tmp_vec = None;
         let mut curr_shop = None;
+        let mut skip_block = false;
         for row in rows.iter() {
-            if row.split_whitespace().count() == 1 {
+            if row.len() == 1 {
                 // escave name detected
                 if let (Some(k), Some(v)) = (curr_shop.take(), tmp_vec.take()) {
                     prices.insert(k, v);
                 }
 
+                // повторный блок не заменяет первый: он пропускается
+                let shop = row.tokens()[0].value().into();
+                if prices.contains_key(&shop) {
+                    diagnostics.push(row.error(PriceParseError::DuplicateTitle));
+                    skip_block = true;
+                    continue;
+                }
+
                 tmp_vec = Some(vec![]);
-                curr_shop = Some(row.to_owned());
+                curr_shop = Some(shop);
+                skip_block = false;
                 continue;
             }
 
             if curr_shop.is_none() {
-                Err(PriceParseError::ExpectedTitleBlock)?
+                // до следующего заголовка блока строки пропускаются
+                if !skip_block {
+                    diagnostics.push(row.error(PriceParseError::ExpectedTitleBlock));
+                    skip_block = true;
+                }
+                continue;
             }
 
-            let price = Price::from_prmrow(row)?;
-            tmp_vec.as_mut().unwrap().push(price);
+            match Price::from_prmrow(row) {
+                Ok(v) => tmp_vec.as_mut().unwrap().push(v),
+                Err(e) => diagnostics.push(e),
+            }
         }
 
         if let (Some(k), Some(v)) = (curr_shop.take(), tmp_vec.take()) {
             prices.insert(k, v);
         }
 
-        Ok(Self { prices })
+        Recovered {
+            table: Self { prices },
+            diagnostics,
+        }
+    }
+}
+
+impl<S: AsRef<str>> ToPrmRows for TablePrice<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![];
+        for (shop, prices) in &self.prices {
+            rows.push(vec![quote(shop.as_ref())]);
+            rows.extend(prices.iter().map(Price::to_prmrow));
+        }
+        rows
     }
 }
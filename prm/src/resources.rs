@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    read_limited, Encoding, Limits, Location, PrmDocument, PrmFile, PrmOpenError, PrmParseError,
+    PrmWrite, ResourceSource, Span, TableBunch, TableEscave, TableItem, TableLocations,
+    TableMechos, TablePassage, TablePrice, TableSpot, TableTabutask, TableVangersWeight,
+    TableWorld,
+};
+
+/// Все PRM-таблицы папки ресурсов.
+///
+/// Таблица, файла которой нет в источнике, остаётся `None` и не записывается
+/// обратно. Таблицы записываются в каноническом виде ([`PrmWrite`]).
+///
+/// Сохранение форматирования включается отдельно: [`ResourceSet::load_layout`]
+/// запоминает исходные файлы таблиц ([`PrmDocument`]), и значения таблиц
+/// переносятся в них ([`PrmDocument::update`]) — набор, не изменённый после
+/// загрузки, записывается байт в байт как исходные файлы. В JSON, TOML и RON
+/// исходные файлы не попадают: чтобы после перевода туда и обратно получить
+/// те же байты, форматирование загружается из исходной папки заново.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct ResourceSet {
+    /// Кодировка, в которой записываются файлы
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encoding: Encoding,
+    /// `car.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub mechos: Option<TableMechos>,
+    /// `item.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub items: Option<TableItem>,
+    /// `worlds.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub worlds: Option<TableWorld>,
+    /// `passages.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub passages: Option<TablePassage>,
+    /// `escaves.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub escaves: Option<TableEscave>,
+    /// `spot.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub spots: Option<TableSpot>,
+    /// `price.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub prices: Option<TablePrice>,
+    /// `bunches.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub bunches: Option<TableBunch>,
+    /// `tabutask.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub tabutasks: Option<TableTabutask>,
+    /// `vangers.prm`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub vangers: Option<TableVangersWeight>,
+    /// Исходные файлы таблиц: имя файла -> документ; пусто, пока
+    /// форматирование не загружено ([`ResourceSet::load_layout`])
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "cache", bincode(with_serde))]
+    pub documents: IndexMap<String, PrmDocument>,
+}
+
+impl ResourceSet {
+    /// Загружает все таблицы, файлы которых есть в источнике `source`;
+    /// кодировка набора — кодировка первого найденного файла
+    pub fn load<S: ResourceSource + ?Sized>(source: &S) -> Result<Self, PrmParseError> {
+        // поля заполняются по порядку: кодировка известна в конце
+        let mut encoding = None;
+        Ok(Self {
+            mechos: load_table(source, &mut encoding)?,
+            items: load_table(source, &mut encoding)?,
+            worlds: load_table(source, &mut encoding)?,
+            passages: load_table(source, &mut encoding)?,
+            escaves: load_table(source, &mut encoding)?,
+            spots: load_table(source, &mut encoding)?,
+            prices: load_table(source, &mut encoding)?,
+            bunches: load_table(source, &mut encoding)?,
+            tabutasks: load_table(source, &mut encoding)?,
+            vangers: load_table(source, &mut encoding)?,
+            encoding: encoding.unwrap_or_default(),
+            documents: IndexMap::new(),
+        })
+    }
+
+    /// Запоминает форматирование таблиц набора: исходные файлы из `source`,
+    /// обычно той же папки, из которой набор загружен.
+    ///
+    /// Файлы таблиц, которых нет в наборе или в источнике, не читаются.
+    /// Если строки таблицы не ложатся на её исходный файл, запись набора
+    /// ([`ResourceSet::to_files`]) возвращает ошибку; чтобы записать такую
+    /// таблицу в каноническом виде, её документ удаляется из `documents`.
+    pub fn load_layout<S: ResourceSource + ?Sized>(
+        &mut self,
+        source: &S,
+    ) -> Result<(), PrmParseError> {
+        let tables = [
+            (TableMechos::file_name(), self.mechos.is_some()),
+            (TableItem::file_name(), self.items.is_some()),
+            (TableWorld::file_name(), self.worlds.is_some()),
+            (TablePassage::file_name(), self.passages.is_some()),
+            (TableEscave::file_name(), self.escaves.is_some()),
+            (TableSpot::file_name(), self.spots.is_some()),
+            (TablePrice::file_name(), self.prices.is_some()),
+            (TableBunch::file_name(), self.bunches.is_some()),
+            (TableTabutask::file_name(), self.tabutasks.is_some()),
+            (TableVangersWeight::file_name(), self.vangers.is_some()),
+        ];
+        for (name, _) in tables.iter().filter(|(_, present)| *present) {
+            if let Some(bytes) = read_file(source, name)? {
+                let document = PrmDocument::decode(name, &bytes, None).table;
+                self.documents.insert((*name).to_owned(), document);
+            }
+        }
+        Ok(())
+    }
+
+    /// Содержимое файлов набора в кодировке набора: имя файла -> содержимое.
+    ///
+    /// Результат сам является источником ресурсов ([`ResourceSource`]).
+    pub fn to_files(&self) -> io::Result<HashMap<String, Vec<u8>>> {
+        let mut files = HashMap::new();
+        self.insert_file(&mut files, &self.mechos)?;
+        self.insert_file(&mut files, &self.items)?;
+        self.insert_file(&mut files, &self.worlds)?;
+        self.insert_file(&mut files, &self.passages)?;
+        self.insert_file(&mut files, &self.escaves)?;
+        self.insert_file(&mut files, &self.spots)?;
+        self.insert_file(&mut files, &self.prices)?;
+        self.insert_file(&mut files, &self.bunches)?;
+        self.insert_file(&mut files, &self.tabutasks)?;
+        self.insert_file(&mut files, &self.vangers)?;
+        Ok(files)
+    }
+
+    /// Записывает файлы набора в папку `path_to_folder`
+    pub fn save<P: AsRef<Path>>(&self, path_to_folder: P) -> io::Result<()> {
+        for (name, bytes) in self.to_files()? {
+            std::fs::write(path_to_folder.as_ref().join(name), bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Все торговые точки набора: эскейвы и споты вместе
+    pub fn locations(&self) -> TableLocations<&str> {
+        let escaves = self.escaves.iter().flat_map(|table| &table.escaves);
+        let spots = self.spots.iter().flat_map(|table| &table.spots);
+        TableLocations {
+            locations: escaves.chain(spots).map(Location::as_borrowed).collect(),
+        }
+    }
+
+    /// Записывает таблицу в `files`: по исходному файлу, если
+    /// форматирование загружено, иначе в каноническом виде
+    fn insert_file<T: PrmFile + PrmWrite>(
+        &self,
+        files: &mut HashMap<String, Vec<u8>>,
+        table: &Option<T>,
+    ) -> io::Result<()> {
+        let table = match table {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+
+        let name = T::file_name();
+        let bytes = match self.documents.get(name) {
+            Some(document) => {
+                let mut document = document.clone();
+                document.set_encoding(self.encoding);
+                document.update(table).map_err(|e| {
+                    io::Error::new(ErrorKind::InvalidData, format!("`{}`: {}", name, e))
+                })?;
+                document.to_bytes()?
+            }
+            None => {
+                let mut bytes = vec![];
+                table.write_encoded(&mut bytes, self.encoding)?;
+                bytes
+            }
+        };
+        files.insert(name.to_owned(), bytes);
+        Ok(())
+    }
+}
+
+/// Загружает таблицу; `None`, если файла нет в источнике. `encoding` —
+/// кодировка первого найденного файла
+fn load_table<T: PrmFile, S: ResourceSource + ?Sized>(
+    source: &S,
+    encoding: &mut Option<Encoding>,
+) -> Result<Option<T>, PrmParseError> {
+    let bytes = match read_file(source, T::file_name())? {
+        Some(bytes) => bytes,
+        None => return Ok(None),
+    };
+    encoding.get_or_insert_with(|| Encoding::detect(&bytes));
+    let table = T::parse_rows(&T::read_rows(bytes.as_slice())?)?;
+    Ok(Some(table))
+}
+
+/// Содержимое файла `name`; `None`, если файла нет в источнике
+fn read_file<S: ResourceSource + ?Sized>(
+    source: &S,
+    name: &str,
+) -> Result<Option<Vec<u8>>, PrmParseError> {
+    let fin = match source.open(name) {
+        Ok(fin) => fin,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(PrmParseError {
+                span: Span::file(name),
+                kind: PrmOpenError::IO(e).into(),
+            })
+        }
+    };
+    read_limited(name, fin, &Limits::default()).map(Some)
+}
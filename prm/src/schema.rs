@@ -0,0 +1,63 @@
+//! JSON Schema структурированного вида таблиц (возможность `schema`).
+//!
+//! Схемы описывают тот же вид, что пишут `serde` и `ResourceSet::to_json`:
+//! имена полей, варианты перечислений и границы чисел по их типам в Rust
+//! (`u8` — от 0 до 255, `u32` — от 0 до 4294967295, `i32` — со знаком).
+
+use schemars::{generate::SchemaSettings, transform::RecursiveTransform, JsonSchema, Schema};
+
+use crate::{
+    Bunch, Cult, CultGame, CultStage, IndexMap, Item, Location, Mechos, Passage, Price,
+    ResourceSet, TableVangersWeight, Tabutask, World,
+};
+
+impl ResourceSet {
+    /// JSON Schema документа набора ресурсов
+    pub fn json_schema() -> Schema {
+        schema_for::<Self>()
+    }
+}
+
+/// JSON Schema строк таблиц по их именам в `snake_case`: `mechos`, `item`,
+/// `bunch`, `cult`, `cult_stage`, `cult_game`, `location`, `passage`,
+/// `price`, `tabutask`, `world` и `vangers_weight`
+pub fn json_schemas() -> IndexMap<&'static str, Schema> {
+    let mut schemas = IndexMap::new();
+    schemas.insert("mechos", schema_for::<Mechos>());
+    schemas.insert("item", schema_for::<Item>());
+    schemas.insert("bunch", schema_for::<Bunch>());
+    schemas.insert("cult", schema_for::<Cult>());
+    schemas.insert("cult_stage", schema_for::<CultStage>());
+    schemas.insert("cult_game", schema_for::<CultGame>());
+    schemas.insert("location", schema_for::<Location>());
+    schemas.insert("passage", schema_for::<Passage>());
+    schemas.insert("price", schema_for::<Price>());
+    schemas.insert("tabutask", schema_for::<Tabutask>());
+    schemas.insert("world", schema_for::<World>());
+    schemas.insert("vangers_weight", schema_for::<TableVangersWeight>());
+    schemas
+}
+
+fn schema_for<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .with_transform(RecursiveTransform(integer_range))
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// Дописывает целым числам границы их типа: `schemars` пишет только
+/// формат (`uint32`, `int32`), а у беззнаковых — лишь нижнюю границу
+fn integer_range(schema: &mut Schema) {
+    let range = match schema.get("format").and_then(|format| format.as_str()) {
+        Some("uint8") => (u8::MIN.into(), u8::MAX.into()),
+        Some("uint16") => (u16::MIN.into(), u16::MAX.into()),
+        Some("uint32") => (u32::MIN.into(), u32::MAX.into()),
+        Some("int8") => (i8::MIN.into(), i8::MAX.into()),
+        Some("int16") => (i16::MIN.into(), i16::MAX.into()),
+        Some("int32") => (i32::MIN.into(), i32::MAX.into()),
+        _ => return,
+    };
+    let (minimum, maximum): (i64, i64) = range;
+    schema.insert("minimum".to_owned(), minimum.into());
+    schema.insert("maximum".to_owned(), maximum.into());
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Cursor},
+    path::{Path, PathBuf},
+};
+
+/// Источник файлов ресурсов игры, из которого загружаются PRM-таблицы
+pub trait ResourceSource {
+    /// Открывает на чтение файл `name` (например, `car.prm`)
+    fn open(&self, name: &str) -> io::Result<Box<dyn BufRead + '_>>;
+}
+
+/// Распакованная папка с ресурсами (например, `resource/`)
+#[derive(Debug, Clone)]
+pub struct DirSource {
+    path: PathBuf,
+}
+
+impl DirSource {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ResourceSource for DirSource {
+    fn open(&self, name: &str) -> io::Result<Box<dyn BufRead + '_>> {
+        let file = std::fs::File::open(self.path.join(name))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Файлы, хранящиеся в памяти: имя файла -> содержимое
+impl ResourceSource for HashMap<String, Vec<u8>> {
+    fn open(&self, name: &str) -> io::Result<Box<dyn BufRead + '_>> {
+        self.get(name)
+            .map(|bytes| Box::new(Cursor::new(bytes.as_slice())) as Box<dyn BufRead>)
+            .ok_or_else(|| not_found(name))
+    }
+}
+
+/// Zip-архив с ресурсами (например, упакованный мод)
+#[cfg(feature = "zip")]
+pub struct ZipSource {
+    archive: std::cell::RefCell<zip::ZipArchive<BufReader<std::fs::File>>>,
+    folder: String,
+    /// наибольший размер файла, распаковываемого в память
+    max_file_size: usize,
+}
+
+#[cfg(feature = "zip")]
+impl ZipSource {
+    /// Открывает архив `path`; файлы ищутся в корне архива
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let archive = zip::ZipArchive::new(BufReader::new(file)).map_err(zip_error)?;
+
+        Ok(Self {
+            archive: std::cell::RefCell::new(archive),
+            folder: String::new(),
+            max_file_size: crate::Limits::default().max_file_size,
+        })
+    }
+
+    /// Искать файлы в папке `folder` внутри архива (например, `resource`)
+    pub fn with_folder(mut self, folder: &str) -> Self {
+        let folder = folder.trim_matches('/');
+        self.folder = if folder.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", folder)
+        };
+        self
+    }
+
+    /// Наибольший размер файла архива, который распаковывается в память
+    /// (по умолчанию [`Limits::max_file_size`](crate::Limits::max_file_size)
+    /// из `Limits::default()`); открытие более длинного файла — ошибка
+    /// [`io::ErrorKind::InvalidData`]. Ограничения, переданные
+    /// в [`PrmFile::load_limited`](crate::PrmFile::load_limited), этот размер
+    /// не меняют
+    pub fn with_max_file_size(mut self, max_file_size: usize) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+}
+
+#[cfg(feature = "zip")]
+impl ResourceSource for ZipSource {
+    fn open(&self, name: &str) -> io::Result<Box<dyn BufRead + '_>> {
+        use std::io::Read;
+
+        let mut archive = self.archive.borrow_mut();
+        let entry = match archive.by_name(&format!("{}{}", self.folder, name)) {
+            Ok(entry) => entry,
+            Err(zip::result::ZipError::FileNotFound) => return Err(not_found(name)),
+            Err(e) => return Err(zip_error(e)),
+        };
+
+        // файл не обрезается молча: на байт больше ограничения — ошибка
+        let mut bytes = vec![];
+        let max_len = self.max_file_size.saturating_add(1) as u64;
+        entry.take(max_len).read_to_end(&mut bytes)?;
+        if bytes.len() > self.max_file_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "file `{}` in the archive is larger than {} bytes",
+                    name, self.max_file_size
+                ),
+            ));
+        }
+
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+}
+
+#[cfg(feature = "zip")]
+fn zip_error(e: zip::result::ZipError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("file `{}` not found in the resource source", name),
+    )
+}
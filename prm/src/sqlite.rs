@@ -0,0 +1,580 @@
+//! Выгрузка набора ресурсов в базу SQLite (возможность `sqlite`).
+//!
+//! Каждой PRM-таблице соответствует таблица базы, связи между ними
+//! записаны внешними ключами по названиям: цены ссылаются на эскейвы
+//! и товары, банчи и задания — на эскейвы, культовые гонки — на эскейвы
+//! и товары, эскейвы, споты и коридоры — на миры, товары эскейвов
+//! и спотов — на товары и пункты назначения. Эскейвы и споты вместе
+//! перечислены в таблице `locations`, на которую ссылаются пункты
+//! назначения.
+//!
+//! Названия миров, предметов, торговых точек и эскейвов банчей — ключи
+//! таблиц базы. Разборщики PRM-файлов повторы названий пропускают, как
+//! и для остальных таблиц; выгрузка набора с повтором (в том числе эскейва
+//! и спота с одним названием) останавливается ошибкой
+//! [`SqliteError::DuplicateName`].
+//!
+//! Перед завершением выгрузки внешние ключи проверяются запросом
+//! `PRAGMA foreign_key_check`: ссылка на отсутствующую строку — ошибка
+//! [`SqliteError::ForeignKey`]. Не проверяются только ссылки на таблицы,
+//! файлов которых нет в наборе.
+//!
+//! Выгрузка удаляет и создаёт заново все таблицы схемы (`worlds`, `items`,
+//! `mechos`, `locations`, `escaves`, `spots`, `goods`, `prices`, `bunches`,
+//! `cults`, `cult_games`, `tabutasks`, `vangers`, `vangers_weights`,
+//! `passages` и справочники `mechos_groups`, `mechos_types`, `bioses`) —
+//! таблицы с такими именами, созданные в базе не этой библиотекой,
+//! удаляются вместе с данными. Поэтому выгружать набор лучше в новую,
+//! отдельную базу.
+
+use std::{collections::HashSet, path::Path};
+
+use rusqlite::{params, types::Value, Connection, Transaction};
+
+use crate::{Bios, CultGame, LocationKind, MechosGroup, ResourceSet, Type};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SqliteError {
+    #[error("{0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("`{table}.{column}` = {value} refers to a missing row of `{parent}`")]
+    ForeignKey {
+        table: String,
+        column: String,
+        value: String,
+        parent: String,
+    },
+    #[error("`{table}`: name `{name}` is used more than once")]
+    DuplicateName { table: String, name: String },
+}
+
+/// Схема базы; таблицы пересоздаются при каждой выгрузке, поэтому
+/// одноимённые таблицы базы теряют свои данные
+const SCHEMA: &str = "
+DROP TABLE IF EXISTS vangers_weights;
+DROP TABLE IF EXISTS vangers;
+DROP TABLE IF EXISTS tabutasks;
+DROP TABLE IF EXISTS cult_games;
+DROP TABLE IF EXISTS cults;
+DROP TABLE IF EXISTS bunches;
+DROP TABLE IF EXISTS prices;
+DROP TABLE IF EXISTS goods;
+DROP TABLE IF EXISTS spots;
+DROP TABLE IF EXISTS escaves;
+DROP TABLE IF EXISTS locations;
+DROP TABLE IF EXISTS passages;
+DROP TABLE IF EXISTS mechos;
+DROP TABLE IF EXISTS items;
+DROP TABLE IF EXISTS worlds;
+DROP TABLE IF EXISTS bioses;
+DROP TABLE IF EXISTS mechos_types;
+DROP TABLE IF EXISTS mechos_groups;
+
+CREATE TABLE mechos_groups (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE mechos_types (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE bioses (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE worlds (
+    name TEXT PRIMARY KEY,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL
+);
+
+CREATE TABLE items (
+    name TEXT PRIMARY KEY,
+    type INTEGER NOT NULL,
+    steeler_full INTEGER NOT NULL,
+    steeler_empty INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    param1 INTEGER NOT NULL,
+    param2 INTEGER NOT NULL
+);
+
+CREATE TABLE mechos (
+    id INTEGER PRIMARY KEY,
+    counter_group INTEGER NOT NULL REFERENCES mechos_groups (id),
+    name TEXT NOT NULL,
+    type INTEGER NOT NULL REFERENCES mechos_types (id),
+    price_buy INTEGER NOT NULL,
+    price_sell INTEGER NOT NULL,
+    box_1 INTEGER NOT NULL,
+    box_2 INTEGER NOT NULL,
+    box_3 INTEGER NOT NULL,
+    box_4 INTEGER NOT NULL,
+    speed INTEGER NOT NULL,
+    armor INTEGER NOT NULL,
+    energy INTEGER NOT NULL,
+    energy_delta INTEGER NOT NULL,
+    energy_drop INTEGER NOT NULL,
+    drop_time INTEGER NOT NULL,
+    fire INTEGER NOT NULL,
+    water INTEGER NOT NULL,
+    oxygen INTEGER NOT NULL,
+    fly INTEGER NOT NULL,
+    damage INTEGER NOT NULL,
+    teleport INTEGER NOT NULL
+);
+
+CREATE TABLE passages (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    world_source TEXT NOT NULL REFERENCES worlds (name),
+    world_destination TEXT NOT NULL REFERENCES worlds (name),
+    pos_x INTEGER NOT NULL,
+    pos_y INTEGER NOT NULL
+);
+
+CREATE TABLE locations (
+    name TEXT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('escave', 'spot'))
+);
+
+CREATE TABLE escaves (
+    name TEXT PRIMARY KEY REFERENCES locations (name),
+    world TEXT NOT NULL REFERENCES worlds (name),
+    pos_x INTEGER NOT NULL,
+    pos_y INTEGER NOT NULL,
+    personal_item TEXT REFERENCES items (name)
+);
+
+CREATE TABLE spots (
+    name TEXT PRIMARY KEY REFERENCES locations (name),
+    world TEXT NOT NULL REFERENCES worlds (name),
+    pos_x INTEGER NOT NULL,
+    pos_y INTEGER NOT NULL,
+    personal_item TEXT REFERENCES items (name)
+);
+
+CREATE TABLE goods (
+    id INTEGER PRIMARY KEY,
+    location TEXT NOT NULL REFERENCES locations (name),
+    item TEXT NOT NULL REFERENCES items (name),
+    destination TEXT NOT NULL REFERENCES locations (name)
+);
+
+CREATE TABLE prices (
+    id INTEGER PRIMARY KEY,
+    escave TEXT NOT NULL REFERENCES escaves (name),
+    item TEXT NOT NULL REFERENCES items (name),
+    buy INTEGER NOT NULL,
+    sell INTEGER NOT NULL
+);
+
+CREATE TABLE bunches (
+    escave TEXT PRIMARY KEY REFERENCES escaves (name),
+    bios INTEGER NOT NULL REFERENCES bioses (id)
+);
+
+CREATE TABLE cults (
+    id INTEGER PRIMARY KEY,
+    escave TEXT NOT NULL REFERENCES bunches (escave),
+    cycle INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    cirt INTEGER NOT NULL,
+    time INTEGER NOT NULL,
+    price INTEGER NOT NULL,
+    palette TEXT NOT NULL
+);
+
+CREATE TABLE cult_games (
+    cult INTEGER PRIMARY KEY REFERENCES cults (id),
+    type TEXT NOT NULL CHECK (type IN ('HARVEST', 'RACE')),
+    source TEXT REFERENCES escaves (name),
+    goods_begin TEXT NOT NULL REFERENCES items (name),
+    goods_begin_count INTEGER NOT NULL,
+    destination TEXT NOT NULL REFERENCES escaves (name),
+    goods_end TEXT REFERENCES items (name),
+    goods_end_count INTEGER,
+    rotten_goods TEXT NOT NULL REFERENCES items (name)
+);
+
+CREATE TABLE tabutasks (
+    id INTEGER PRIMARY KEY,
+    escave TEXT NOT NULL REFERENCES escaves (name),
+    cash INTEGER NOT NULL,
+    luck INTEGER NOT NULL,
+    cycle INTEGER NOT NULL,
+    target INTEGER NOT NULL,
+    work INTEGER NOT NULL,
+    item TEXT NOT NULL REFERENCES items (name),
+    count INTEGER NOT NULL
+);
+
+CREATE TABLE vangers (
+    total INTEGER NOT NULL
+);
+
+CREATE TABLE vangers_weights (
+    world TEXT PRIMARY KEY REFERENCES worlds (name),
+    weight INTEGER NOT NULL
+);
+";
+
+impl ResourceSet {
+    /// Выгружает набор в базу SQLite по пути `path`.
+    ///
+    /// Таблицы схемы, уже существующие в базе, удаляются вместе с данными
+    /// и создаются заново, даже если их создала не эта библиотека.
+    pub fn to_sqlite<P: AsRef<Path>>(&self, path: P) -> Result<(), SqliteError> {
+        let mut connection = Connection::open(path)?;
+        self.write_sqlite(&mut connection)
+    }
+
+    /// Выгружает набор в открытую базу одной транзакцией; при ошибке база
+    /// не меняется. Таблицы схемы пересоздаются, как в
+    /// [`ResourceSet::to_sqlite`].
+    pub fn write_sqlite(&self, connection: &mut Connection) -> Result<(), SqliteError> {
+        // внутри транзакции эта настройка не меняется
+        let foreign_keys: bool =
+            connection.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+        connection.execute_batch("PRAGMA foreign_keys = OFF")?;
+        let result = self.write_tables(connection);
+        connection.execute_batch(if foreign_keys {
+            "PRAGMA foreign_keys = ON"
+        } else {
+            "PRAGMA foreign_keys = OFF"
+        })?;
+        result
+    }
+
+    fn write_tables(&self, connection: &mut Connection) -> Result<(), SqliteError> {
+        self.check_names()?;
+
+        let tx = connection.transaction()?;
+        tx.execute_batch(SCHEMA)?;
+
+        insert_names(&tx, "mechos_groups", MechosGroup::NAMES)?;
+        insert_names(&tx, "mechos_types", Type::NAMES)?;
+        insert_names(&tx, "bioses", Bios::NAMES)?;
+
+        self.insert_worlds(&tx)?;
+        self.insert_items(&tx)?;
+        self.insert_mechos(&tx)?;
+        self.insert_passages(&tx)?;
+        self.insert_locations(&tx)?;
+        self.insert_prices(&tx)?;
+        self.insert_bunches(&tx)?;
+        self.insert_tabutasks(&tx)?;
+        self.insert_vangers(&tx)?;
+
+        self.check_foreign_keys(&tx)?;
+        Ok(tx.commit()?)
+    }
+
+    /// Проверяет, что названия-ключи не повторяются: ошибка — первый повтор
+    fn check_names(&self) -> Result<(), SqliteError> {
+        let worlds = self.worlds.iter().flat_map(|table| &table.worlds);
+        unique_names("worlds", worlds.map(|w| w.name.as_str()))?;
+        let items = self.items.iter().flat_map(|table| &table.items);
+        unique_names("items", items.map(|i| i.name.as_str()))?;
+        let locations = self.locations().locations;
+        unique_names("locations", locations.iter().map(|l| l.name))?;
+        let bunches = self.bunches.iter().flat_map(|table| &table.bunches);
+        unique_names("bunches", bunches.map(|b| b.escave_name.as_str()))
+    }
+
+    /// Проверяет внешние ключи; ошибка — первая ссылка на отсутствующую
+    /// строку таблицы, файл которой есть в наборе
+    fn check_foreign_keys(&self, tx: &Transaction<'_>) -> Result<(), SqliteError> {
+        let mut check = tx.prepare("PRAGMA foreign_key_check")?;
+        let mut violations = check.query([])?;
+        while let Some(violation) = violations.next()? {
+            let table: String = violation.get(0)?;
+            let rowid: i64 = violation.get(1)?;
+            let parent: String = violation.get(2)?;
+            let key: i64 = violation.get(3)?;
+            if !self.has_files_of(&parent) {
+                continue;
+            }
+
+            let column: String = tx.query_row(
+                "SELECT \"from\" FROM pragma_foreign_key_list(?1) WHERE id = ?2",
+                params![table, key],
+                |row| row.get(0),
+            )?;
+            let value: Value = tx.query_row(
+                &format!(
+                    "SELECT {} FROM {} WHERE rowid = ?1",
+                    quote_identifier(&column),
+                    quote_identifier(&table)
+                ),
+                [rowid],
+                |row| row.get(0),
+            )?;
+            let value = match value {
+                Value::Text(text) => format!("`{}`", text),
+                Value::Integer(number) => number.to_string(),
+                value => format!("{:?}", value),
+            };
+            return Err(SqliteError::ForeignKey {
+                table,
+                column,
+                value,
+                parent,
+            });
+        }
+        Ok(())
+    }
+
+    /// Есть ли в наборе файлы, из которых заполняется таблица базы `table`
+    fn has_files_of(&self, table: &str) -> bool {
+        match table {
+            "worlds" => self.worlds.is_some(),
+            "items" => self.items.is_some(),
+            "escaves" => self.escaves.is_some(),
+            "locations" => self.escaves.is_some() && self.spots.is_some(),
+            "bunches" | "cults" => self.bunches.is_some(),
+            _ => true,
+        }
+    }
+
+    fn insert_worlds(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut insert = tx.prepare("INSERT INTO worlds VALUES (?1, ?2, ?3)")?;
+        for world in self.worlds.iter().flat_map(|table| &table.worlds) {
+            insert.execute(params![world.name, world.width, world.height])?;
+        }
+        Ok(())
+    }
+
+    fn insert_items(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut insert = tx.prepare("INSERT INTO items VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+        for item in self.items.iter().flat_map(|table| &table.items) {
+            insert.execute(params![
+                item.name,
+                item.r#type,
+                item.steeler.full,
+                item.steeler.empty,
+                item.size,
+                item.count,
+                item.param1,
+                item.param2,
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn insert_mechos(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let table = match &self.mechos {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+
+        let mut insert = tx.prepare(
+            "INSERT INTO mechos VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, \
+             ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+        )?;
+        for (id, (group, m)) in table.groups().zip(&table.mechoses).enumerate() {
+            insert.execute(params![
+                id as i64,
+                group as u8,
+                m.name,
+                m.r#type as u8,
+                m.price.buy,
+                m.price.sell,
+                m.r#box.0,
+                m.r#box.1,
+                m.r#box.2,
+                m.r#box.3,
+                m.speed,
+                m.armor,
+                m.energy,
+                m.energy_delta,
+                m.energy_drop,
+                m.drop_time,
+                m.fire,
+                m.water,
+                m.oxygen,
+                m.fly,
+                m.damage,
+                m.teleport,
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn insert_passages(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut insert = tx.prepare("INSERT INTO passages VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        let passages = self.passages.iter().flat_map(|table| &table.passages);
+        for (id, passage) in passages.enumerate() {
+            insert.execute(params![
+                id as i64,
+                passage.name,
+                passage.world_src_name,
+                passage.world_dest_name,
+                passage.pos_x,
+                passage.pos_y,
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Эскейвы, споты и их товары
+    fn insert_locations(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut location = tx.prepare("INSERT INTO locations VALUES (?1, ?2)")?;
+        let mut escave = tx.prepare("INSERT INTO escaves VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        let mut spot = tx.prepare("INSERT INTO spots VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        let mut goods =
+            tx.prepare("INSERT INTO goods (location, item, destination) VALUES (?1, ?2, ?3)")?;
+
+        for l in &self.locations().locations {
+            location.execute(params![l.name, l.kind.name()])?;
+            let insert = match l.kind {
+                LocationKind::Escave => &mut escave,
+                LocationKind::Spot => &mut spot,
+            };
+            insert.execute(params![
+                l.name,
+                l.world_name,
+                l.pos_x,
+                l.pos_y,
+                l.personal_item_name,
+            ])?;
+            for (item, destination) in &l.goods {
+                goods.execute(params![l.name, item, destination])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_prices(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut insert =
+            tx.prepare("INSERT INTO prices (escave, item, buy, sell) VALUES (?1, ?2, ?3, ?4)")?;
+        for (escave, prices) in self.prices.iter().flat_map(|table| &table.prices) {
+            for price in prices {
+                insert.execute(params![escave, price.name, price.buy, price.sell])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Банчи, их циклы и культовые гонки
+    fn insert_bunches(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut bunch = tx.prepare("INSERT INTO bunches VALUES (?1, ?2)")?;
+        let mut cult = tx.prepare(
+            "INSERT INTO cults (escave, cycle, name, cirt, time, price, palette) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        let mut game =
+            tx.prepare("INSERT INTO cult_games VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+
+        for b in self.bunches.iter().flat_map(|table| &table.bunches) {
+            bunch.execute(params![b.escave_name, b.bios as u8])?;
+
+            for (cycle, c) in b.cults.iter().enumerate() {
+                let stage = c.stage();
+                cult.execute(params![
+                    b.escave_name,
+                    cycle as i64,
+                    stage.name,
+                    stage.cirt,
+                    stage.time,
+                    stage.price,
+                    stage.palette,
+                ])?;
+                let id = tx.last_insert_rowid();
+
+                match c.game() {
+                    None => {}
+                    Some(CultGame::Harvest(g)) => {
+                        game.execute(params![
+                            id,
+                            "HARVEST",
+                            None::<&str>,
+                            g.goods_type_name,
+                            g.goods_count,
+                            g.destination_name,
+                            None::<&str>,
+                            None::<u32>,
+                            g.rotten_goods_type_name,
+                        ])?;
+                    }
+                    Some(CultGame::Race(g)) => {
+                        game.execute(params![
+                            id,
+                            "RACE",
+                            g.source_name,
+                            g.goods_type_beg_name,
+                            g.goods_count_beg,
+                            g.destination_name,
+                            g.goods_type_end_name,
+                            g.goods_count_end,
+                            g.rotten_goods_type_name,
+                        ])?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_tabutasks(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let mut insert = tx.prepare(
+            "INSERT INTO tabutasks (escave, cash, luck, cycle, target, work, item, count) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for (escave, tabutasks) in self.tabutasks.iter().flat_map(|table| &table.tabutasks) {
+            for t in tabutasks {
+                insert.execute(params![
+                    escave, t.cash, t.luck, t.cycle, t.target, t.work, t.item, t.count,
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_vangers(&self, tx: &Transaction<'_>) -> rusqlite::Result<()> {
+        let table = match &self.vangers {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+
+        tx.execute("INSERT INTO vangers VALUES (?1)", [table.vangers_total])?;
+        let mut insert = tx.prepare("INSERT INTO vangers_weights VALUES (?1, ?2)")?;
+        for (world, weight) in &table.weights {
+            insert.execute(params![world, weight])?;
+        }
+        Ok(())
+    }
+}
+
+/// Названия вариантов перечисления по их номерам
+fn insert_names(tx: &Transaction<'_>, table: &str, names: &[&str]) -> rusqlite::Result<()> {
+    let sql = format!("INSERT INTO {} VALUES (?1, ?2)", quote_identifier(table));
+    let mut insert = tx.prepare(&sql)?;
+    for (id, name) in names.iter().enumerate() {
+        insert.execute(params![id as i64, name])?;
+    }
+    Ok(())
+}
+
+/// Первый повтор среди названий `names` таблицы базы `table` — ошибка
+fn unique_names<'a, I>(table: &str, names: I) -> Result<(), SqliteError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut seen = HashSet::new();
+    match names.into_iter().find(|name| !seen.insert(*name)) {
+        Some(name) => Err(SqliteError::DuplicateName {
+            table: table.to_owned(),
+            name: name.to_owned(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Имя таблицы или колонки в двойных кавычках, для подстановки в SQL
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -1,5 +1,7 @@
-use crate::{PrmFile, PrmParseError};
-use std::{collections::HashMap, path::Path};
+use crate::{lexer::quote, PrmFile, PrmParseError, PrmTable, Recovered, Row, Rows, ToPrmRows};
+use std::hash::Hash;
+
+use indexmap::IndexMap;
 
 #[derive(Debug, thiserror::Error)]
 pub enum TabutaskParseError {
@@ -21,59 +23,165 @@ pub enum TabutaskParseError {
     UnexpectedAdditionalParameter,
     #[error("title of a block that includes an escave name is not found")]
     ExpectedTitleBlock,
+    #[error("block of the same escave is already defined")]
+    DuplicateTitle,
+}
+
+/// Задание (табутаск) эскейва из файла `tabutask.prm`.
+///
+/// Строка файла: `cash luck cycle target work item count`. Виды цели
+/// и работы хранятся номерами, как в файле: их значения определяет код
+/// игры, и библиотека их не толкует.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct Tabutask<S = String> {
+    /// Награда в деньгах
+    pub cash: u32,
+    /// Изменение удачи за выполнение задания
+    pub luck: i32,
+    /// Цикл, в котором выдаётся задание
+    pub cycle: u32,
+    /// Вид цели задания (номер, как в файле)
+    pub target: u8,
+    /// Вид работы (номер, как в файле)
+    pub work: u8,
+    /// Название товара задания
+    pub item: S,
+    /// Количество товара
+    pub count: u32,
 }
 
-pub struct Tabutask {
-    pub name: String,
-    pub buy: u32,
-    pub sell: u32,
+impl<'a, S: From<&'a str>> Tabutask<S> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
+        use TabutaskParseError::*;
+
+        let mut iter = row.fields();
+
+        let cash = iter.parse(Cash)?;
+        let luck = iter.parse(Luck)?;
+        let cycle = iter.parse(Cycle)?;
+        let target = iter.parse(Target)?;
+        let work = iter.parse(Work)?;
+        let item = iter.word(Item)?.into();
+        let count = iter.parse(Count)?;
+
+        iter.finish(UnexpectedAdditionalParameter)?;
+
+        Ok(Self {
+            cash,
+            luck,
+            cycle,
+            target,
+            work,
+            item,
+            count,
+        })
+    }
 }
 
-impl Tabutask {
-    fn from_prmrow(_row: &str) -> Result<Self, TabutaskParseError> {
-        unimplemented!();
+impl<S: AsRef<str>> Tabutask<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            self.cash.to_string(),
+            self.luck.to_string(),
+            self.cycle.to_string(),
+            self.target.to_string(),
+            self.work.to_string(),
+            quote(self.item.as_ref()),
+            self.count.to_string(),
+        ]
     }
 }
 
-pub struct TableTabutask {
-    pub tabutasks: HashMap<String, Vec<Tabutask>>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "S: serde::Deserialize<'de> + Eq + std::hash::Hash"))
+)]
+#[cfg_attr(
+    feature = "cache",
+    bincode(
+        encode_bounds = "S: serde::Serialize",
+        decode_bounds = "S: serde::de::DeserializeOwned + Eq + std::hash::Hash",
+        borrow_decode_bounds = "S: serde::de::DeserializeOwned + Eq + std::hash::Hash"
+    )
+)]
+pub struct TableTabutask<S = String> {
+    /// Задания по эскейвам в порядке блоков файла
+    #[cfg_attr(feature = "cache", bincode(with_serde))]
+    pub tabutasks: IndexMap<S, Vec<Tabutask<S>>>,
 }
 
 impl PrmFile for TableTabutask {
     fn file_name<'a>() -> &'a str {
         "tabutask.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
-
-        let mut tabutasks = HashMap::new();
+impl<'a, S: From<&'a str> + Eq + Hash> PrmTable<'a> for TableTabutask<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
+        let mut tabutasks = IndexMap::new();
+        let mut diagnostics = vec![];
         let mut tmp_vec = None;
         let mut curr_escave = None;
+        let mut skip_block = false;
         for row in rows.iter() {
-            if row.split_whitespace().count() == 1 {
+            if row.len() == 1 {
                 // escave name detected
                 if let (Some(k), Some(v)) = (curr_escave.take(), tmp_vec.take()) {
                     tabutasks.insert(k, v);
                 }
 
+                // повторный блок не заменяет первый: он пропускается
+                let escave = row.tokens()[0].value().into();
+                if tabutasks.contains_key(&escave) {
+                    diagnostics.push(row.error(TabutaskParseError::DuplicateTitle));
+                    skip_block = true;
+                    continue;
+                }
+
                 tmp_vec = Some(vec![]);
-                curr_escave = Some(row.to_owned());
+                curr_escave = Some(escave);
+                skip_block = false;
                 continue;
             }
 
             if curr_escave.is_none() {
-                Err(TabutaskParseError::ExpectedTitleBlock)?
+                // до следующего заголовка блока строки пропускаются
+                if !skip_block {
+                    diagnostics.push(row.error(TabutaskParseError::ExpectedTitleBlock));
+                    skip_block = true;
+                }
+                continue;
             }
 
-            let tabutask = Tabutask::from_prmrow(row)?;
-            tmp_vec.as_mut().unwrap().push(tabutask);
+            match Tabutask::from_prmrow(row) {
+                Ok(v) => tmp_vec.as_mut().unwrap().push(v),
+                Err(e) => diagnostics.push(e),
+            }
         }
 
         if let (Some(k), Some(v)) = (curr_escave.take(), tmp_vec.take()) {
             tabutasks.insert(k, v);
         }
 
-        Ok(Self { tabutasks })
+        Recovered {
+            table: Self { tabutasks },
+            diagnostics,
+        }
+    }
+}
+
+impl<S: AsRef<str>> ToPrmRows for TableTabutask<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![];
+        for (escave, tabutasks) in &self.tabutasks {
+            rows.push(vec![quote(escave.as_ref())]);
+            rows.extend(tabutasks.iter().map(Tabutask::to_prmrow));
+        }
+        rows
     }
 }
@@ -1,8 +1,8 @@
-// TODO: merge spot & escave parsers
+use std::hash::Hash;
 
-use std::{collections::HashMap, path::Path};
+use indexmap::IndexMap;
 
-use crate::{PrmFile, PrmParseError};
+use crate::{lexer::quote, PrmFile, PrmTable, Recovered, Rows, ToPrmRows};
 
 #[derive(Debug, thiserror::Error)]
 pub enum VangersWeightParseError {
@@ -12,59 +12,101 @@ pub enum VangersWeightParseError {
     RelativeWeight,
     #[error("unexpected additional parameter at vangers_total line")]
     UnexpectedAdditionalParameterAtRelativeWeightLine,
+    #[error("weight of the same world is already defined")]
+    DuplicateWorld,
 }
 
-pub struct TableVangersWeight {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "S: serde::Deserialize<'de> + Eq + std::hash::Hash"))
+)]
+#[cfg_attr(
+    feature = "cache",
+    bincode(
+        encode_bounds = "S: serde::Serialize",
+        decode_bounds = "S: serde::de::DeserializeOwned + Eq + std::hash::Hash",
+        borrow_decode_bounds = "S: serde::de::DeserializeOwned + Eq + std::hash::Hash"
+    )
+)]
+pub struct TableVangersWeight<S = String> {
     /// total c-vangers number in Chain at one moment
     pub vangers_total: u32,
     /// relative weight of total world c-vanger density
-    /// (String) world -> (u32) relative weight
-    pub weights: HashMap<String, u32>,
+    /// (String) world -> (u32) relative weight, in file order
+    #[cfg_attr(feature = "cache", bincode(with_serde))]
+    pub weights: IndexMap<S, u32>,
 }
 
 impl PrmFile for TableVangersWeight {
     fn file_name<'a>() -> &'a str {
         "vangers.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
+impl<'a, S: From<&'a str> + Eq + Hash> PrmTable<'a> for TableVangersWeight<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
         use VangersWeightParseError::*;
 
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
+        let mut diagnostics = vec![];
         let mut iter = rows.iter();
 
-        let vangers_total = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(VangersTotal)?;
+        let vangers_total = iter.next_or(VangersTotal).and_then(|row| {
+            let mut fields = row.fields();
+            let vangers_total = fields.parse(VangersTotal)?;
+            fields.finish(VangersTotal)?;
+            Ok(vangers_total)
+        });
+        let vangers_total = vangers_total.unwrap_or_else(|e| {
+            diagnostics.push(e);
+            0
+        });
 
-        let mut weights = HashMap::new();
-        while let Some(row) = iter.next() {
-            let mut iter = row.split_whitespace();
+        let mut weights = IndexMap::new();
+        for row in iter {
+            let mut iter = row.fields();
 
-            let (world, weight) = iter
-                .next()
-                .and_then(|world| {
-                    iter.next().and_then(|weight| {
-                        if let Ok(weight) = weight.parse() {
-                            Some((world, weight))
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .ok_or(RelativeWeight)?;
+            let weight = iter.word(RelativeWeight).and_then(|world| {
+                let weight = iter.parse(RelativeWeight)?;
+                iter.finish(UnexpectedAdditionalParameterAtRelativeWeightLine)?;
+                Ok((world, weight))
+            });
 
-            if iter.next().is_some() {
-                Err(UnexpectedAdditionalParameterAtRelativeWeightLine)?
+            match weight {
+                Ok((world, weight)) => {
+                    // повторный мир не заменяет первый
+                    let world: S = world.into();
+                    if weights.contains_key(&world) {
+                        diagnostics.push(row.error(DuplicateWorld));
+                    } else {
+                        weights.insert(world, weight);
+                    }
+                }
+                Err(e) => diagnostics.push(e),
             }
+        }
 
-            weights.insert(world.to_owned(), weight);
+        Recovered {
+            table: Self {
+                vangers_total,
+                weights,
+            },
+            diagnostics,
         }
+    }
+}
 
-        Ok(Self {
-            vangers_total,
-            weights,
-        })
+impl<S: AsRef<str>> ToPrmRows for TableVangersWeight<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        let total = vec![self.vangers_total.to_string()];
+        std::iter::once(total)
+            .chain(
+                self.weights
+                    .iter()
+                    .map(|(world, weight)| vec![quote(world.as_ref()), weight.to_string()]),
+            )
+            .collect()
     }
 }
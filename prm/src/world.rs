@@ -1,6 +1,4 @@
-use std::path::Path;
-
-use crate::{PrmFile, PrmParseError};
+use crate::{lexer::quote, PrmFile, PrmParseError, PrmTable, Recovered, Row, Rows, ToPrmRows};
 
 #[derive(Debug, thiserror::Error)]
 pub enum WorldParseError {
@@ -14,29 +12,24 @@ pub enum WorldParseError {
     UnexpectedAdditionalParameter,
 }
 
-pub struct World {
-    pub name: String,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct World<S = String> {
+    pub name: S,
     pub width: u32,  // x
     pub height: u32, // y
 }
 
-impl World {
-    fn from_prmrow(row: &str) -> Result<Self, WorldParseError> {
-        let mut iter = row.split_whitespace();
+impl<'a, S: From<&'a str>> World<S> {
+    pub(crate) fn from_prmrow(row: &'a Row<'a>) -> Result<Self, PrmParseError> {
+        let mut iter = row.fields();
 
-        let name = iter.next().ok_or(WorldParseError::Name)?.to_owned();
-        let width = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(WorldParseError::Width)?;
-        let height = iter
-            .next()
-            .and_then(|s| s.parse().ok())
-            .ok_or(WorldParseError::Height)?;
+        let name = iter.word(WorldParseError::Name)?.into();
+        let width = iter.parse(WorldParseError::Width)?;
+        let height = iter.parse(WorldParseError::Height)?;
 
-        if iter.next().is_some() {
-            Err(WorldParseError::UnexpectedAdditionalParameter)?
-        }
+        iter.finish(WorldParseError::UnexpectedAdditionalParameter)?;
 
         Ok(Self {
             name,
@@ -46,30 +39,64 @@ impl World {
     }
 }
 
+impl<S: AsRef<str>> World<S> {
+    pub(crate) fn to_prmrow(&self) -> Vec<String> {
+        vec![
+            quote(self.name.as_ref()),
+            self.width.to_string(),
+            self.height.to_string(),
+        ]
+    }
+}
+
 /// Таблица со всеми мирами из `world.prm`
-pub struct TableWorld {
-    worlds: Vec<World>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cache", derive(bincode::Encode, bincode::Decode))]
+pub struct TableWorld<S = String> {
+    pub(crate) worlds: Vec<World<S>>,
 }
 
-impl TableWorld {
-    pub fn worlds(&self) -> &[World] {
+impl<S> TableWorld<S> {
+    pub fn new(worlds: Vec<World<S>>) -> Self {
+        Self { worlds }
+    }
+
+    pub fn worlds(&self) -> &[World<S>] {
         &self.worlds
     }
+
+    pub fn worlds_mut(&mut self) -> &mut Vec<World<S>> {
+        &mut self.worlds
+    }
 }
 
 impl PrmFile for TableWorld {
     fn file_name<'a>() -> &'a str {
         "worlds.prm"
     }
+}
 
-    fn file_parse<P: AsRef<Path>>(path_to_folder: P) -> Result<Self, PrmParseError> {
-        let rows = Self::file_open(path_to_folder).map_err::<PrmParseError, _>(|e| e.into())?;
-
+impl<'a, S: From<&'a str>> PrmTable<'a> for TableWorld<S> {
+    fn parse_rows_recovering(rows: &'a Rows<'_>) -> Recovered<Self> {
         let mut worlds = vec![];
+        let mut diagnostics = vec![];
         for row in rows.iter() {
-            worlds.push(World::from_prmrow(row)?);
+            match World::from_prmrow(row) {
+                Ok(world) => worlds.push(world),
+                Err(e) => diagnostics.push(e),
+            }
         }
 
-        Ok(Self { worlds })
+        Recovered {
+            table: Self { worlds },
+            diagnostics,
+        }
+    }
+}
+
+impl<S: AsRef<str>> ToPrmRows for TableWorld<S> {
+    fn to_prmrows(&self) -> Vec<Vec<String>> {
+        self.worlds.iter().map(World::to_prmrow).collect()
     }
 }
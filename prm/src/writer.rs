@@ -0,0 +1,68 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{Encoding, PrmFile, PrmHeader, ToPrmRows};
+
+/// Запись таблицы в PRM-файл: заголовок (по умолчанию
+/// `uniVang-ParametersFile_Ver_1`) и строки в том виде, в каком их ожидает
+/// разборщик таблицы.
+///
+/// Комментарии и форматирование исходного файла не сохраняются;
+/// для правки существующего файла есть [`PrmDocument::update`].
+///
+/// [`PrmDocument::update`]: crate::PrmDocument::update
+pub trait PrmWrite: ToPrmRows {
+    /// Текст PRM-файла с заголовком по умолчанию
+    fn to_prm_string(&self) -> String {
+        self.to_prm_string_with_header(&PrmHeader::default())
+    }
+
+    /// Текст PRM-файла с заголовком `header` (например, прочитанным
+    /// из исходного файла, [`Rows::header`](crate::Rows::header))
+    fn to_prm_string_with_header(&self, header: &PrmHeader) -> String {
+        let mut text = header.to_string();
+        text.push('\n');
+        for row in self.to_prmrows() {
+            text.push_str(&row.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Записывает таблицу в `fout` в кодировке UTF-8
+    fn write_to<W: Write>(&self, fout: W) -> io::Result<()> {
+        self.write_encoded(fout, Encoding::Utf8)
+    }
+
+    /// Записывает таблицу в `fout` в кодировке `encoding`
+    fn write_encoded<W: Write>(&self, fout: W, encoding: Encoding) -> io::Result<()> {
+        self.write_with_header(fout, &PrmHeader::default(), encoding)
+    }
+
+    /// Записывает таблицу с заголовком `header` в `fout` в кодировке
+    /// `encoding`
+    fn write_with_header<W: Write>(
+        &self,
+        mut fout: W,
+        header: &PrmHeader,
+        encoding: Encoding,
+    ) -> io::Result<()> {
+        fout.write_all(&encoding.encode(&self.to_prm_string_with_header(header))?)
+    }
+
+    /// Сохраняет таблицу в файл `file_name()` в папке `path_to_folder`
+    fn file_save<P: AsRef<Path>>(&self, path_to_folder: P) -> io::Result<()>
+    where
+        Self: PrmFile,
+    {
+        let file = File::create(path_to_folder.as_ref().join(Self::file_name()))?;
+        let mut fout = BufWriter::new(file);
+        self.write_to(&mut fout)?;
+        fout.flush()
+    }
+}
+
+impl<T: ToPrmRows> PrmWrite for T {}
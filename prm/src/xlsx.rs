@@ -0,0 +1,239 @@
+//! Книга XLSX с листом на каждую PRM-таблицу набора ресурсов
+//! (возможность `xlsx`).
+//!
+//! Листы называются по файлам таблиц без `.prm`, колонки у них те же, что
+//! в CSV ([`CsvTable`]). Числа записываются числовыми ячейками, а колонки
+//! с номером варианта (тип мехоса, биос) — названиями вариантов со списком
+//! допустимых значений.
+
+use std::io::{Read, Seek};
+
+use calamine::{Reader, Xlsx};
+use rust_xlsxwriter::{DataValidation, Format, Workbook};
+
+use crate::{
+    csv_table::{check_header, csv_rows},
+    CellKind, CsvError, CsvTable, PrmFile, PrmParseError, ResourceSet,
+};
+
+/// Последняя строка листа XLSX (с нуля)
+const LAST_ROW: u32 = 1_048_575;
+
+#[derive(Debug, thiserror::Error)]
+pub enum XlsxError {
+    #[error("{0}")]
+    Write(#[from] rust_xlsxwriter::XlsxError),
+    #[error("{0}")]
+    Read(#[from] calamine::XlsxError),
+    #[error("{0}")]
+    Table(#[from] CsvError),
+    #[error("{sheet}:{row}:{column}: cell outside of the table columns")]
+    UnexpectedCell {
+        sheet: String,
+        row: usize,
+        column: usize,
+    },
+    /// Ошибки всех неверных строк книги; место ошибки — лист, строка
+    /// и колонка
+    #[error("{}", cells_message(.0))]
+    Cells(Vec<PrmParseError>),
+}
+
+fn cells_message(errors: &[PrmParseError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl ResourceSet {
+    /// Набор в виде книги XLSX
+    pub fn to_xlsx(&self) -> Result<Vec<u8>, XlsxError> {
+        let mut workbook = Workbook::new();
+        add_sheet(&mut workbook, &self.mechos)?;
+        add_sheet(&mut workbook, &self.items)?;
+        add_sheet(&mut workbook, &self.worlds)?;
+        add_sheet(&mut workbook, &self.passages)?;
+        add_sheet(&mut workbook, &self.escaves)?;
+        add_sheet(&mut workbook, &self.spots)?;
+        add_sheet(&mut workbook, &self.prices)?;
+        add_sheet(&mut workbook, &self.bunches)?;
+        add_sheet(&mut workbook, &self.tabutasks)?;
+        add_sheet(&mut workbook, &self.vangers)?;
+        Ok(workbook.save_to_buffer()?)
+    }
+
+    /// Читает набор из книги XLSX; таблица, листа которой нет в книге,
+    /// остаётся `None`. Кодировка набора — кодировка по умолчанию.
+    ///
+    /// Разбор не останавливается на первой неверной ячейке: неверные строки
+    /// пропускаются, и возвращаются ошибки всех неверных строк книги
+    /// (по первой неверной ячейке строки).
+    pub fn from_xlsx<R: Read + Seek>(reader: R) -> Result<Self, XlsxError> {
+        let mut workbook = Xlsx::new(reader)?;
+        let mut errors = vec![];
+
+        let set = Self {
+            mechos: read_sheet(&mut workbook, &mut errors)?,
+            items: read_sheet(&mut workbook, &mut errors)?,
+            worlds: read_sheet(&mut workbook, &mut errors)?,
+            passages: read_sheet(&mut workbook, &mut errors)?,
+            escaves: read_sheet(&mut workbook, &mut errors)?,
+            spots: read_sheet(&mut workbook, &mut errors)?,
+            prices: read_sheet(&mut workbook, &mut errors)?,
+            bunches: read_sheet(&mut workbook, &mut errors)?,
+            tabutasks: read_sheet(&mut workbook, &mut errors)?,
+            vangers: read_sheet(&mut workbook, &mut errors)?,
+            ..Self::default()
+        };
+
+        if errors.is_empty() {
+            Ok(set)
+        } else {
+            Err(XlsxError::Cells(errors))
+        }
+    }
+}
+
+/// Имя листа таблицы: имя её файла без `.prm`
+fn sheet_name<T: PrmFile>() -> &'static str {
+    T::file_name().trim_end_matches(".prm")
+}
+
+fn add_sheet<T: CsvTable + PrmFile>(
+    workbook: &mut Workbook,
+    table: &Option<T>,
+) -> Result<(), XlsxError> {
+    let table = match table {
+        Some(table) => table,
+        None => return Ok(()),
+    };
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(sheet_name::<T>())?;
+
+    let bold = Format::new().set_bold();
+    for (col, column) in T::COLUMNS.iter().enumerate() {
+        let col = col as u16;
+        sheet.write_string_with_format(0, col, column.name, &bold)?;
+        if let CellKind::Enum(variants) = column.kind {
+            let validation = DataValidation::new().allow_list_strings(variants)?;
+            sheet.add_data_validation(1, col, LAST_ROW, col, &validation)?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    for (index, record) in table.to_csv_records().iter().enumerate() {
+        let row = index as u32 + 1;
+        for (col, (value, column)) in record.iter().zip(T::COLUMNS).enumerate() {
+            let col = col as u16;
+            match column.kind {
+                _ if value.is_empty() => {}
+                CellKind::Enum(variants) => {
+                    let name = value.parse::<usize>().ok().and_then(|i| variants.get(i));
+                    sheet.write_string(row, col, name.copied().unwrap_or(value))?;
+                }
+                // число, которое ячейка хранит без потерь, — числовой ячейкой
+                CellKind::Number => match value.parse::<i64>() {
+                    Ok(number) if number.to_string() == *value && number.abs() < 1 << 53 => {
+                        sheet.write_number(row, col, number as f64)?;
+                    }
+                    _ => {
+                        sheet.write_string(row, col, value)?;
+                    }
+                },
+                CellKind::Text => {
+                    sheet.write_string(row, col, value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_sheet<T: CsvTable + PrmFile, R: Read + Seek>(
+    workbook: &mut Xlsx<R>,
+    errors: &mut Vec<PrmParseError>,
+) -> Result<Option<T>, XlsxError> {
+    let name = sheet_name::<T>();
+    if !workbook.sheet_names().iter().any(|sheet| sheet == name) {
+        return Ok(None);
+    }
+
+    let range = workbook.worksheet_range(name)?;
+    let (top, left) = range.start().unwrap_or_default();
+
+    // ячейки листа, начиная с колонки `A`: номер строки -> ячейки
+    let mut lines = range.rows().enumerate().map(|(index, cells)| {
+        let mut row = vec![String::new(); left as usize];
+        row.extend(cells.iter().map(ToString::to_string));
+        (top as usize + index + 1, row)
+    });
+
+    let header = match lines.next() {
+        Some((1, header)) => header,
+        _ => vec![],
+    };
+    let header: Vec<&str> = header.iter().map(String::as_str).collect();
+    let width = header
+        .iter()
+        .rposition(|cell| !cell.is_empty())
+        .map_or(0, |last| last + 1);
+    check_header::<T>(name, &header[..width])?;
+
+    let mut records = vec![];
+    for (line, mut cells) in lines {
+        if cells.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+
+        cells.resize(cells.len().max(width), String::new());
+        if let Some(extra) = cells[width..].iter().position(|cell| !cell.is_empty()) {
+            return Err(XlsxError::UnexpectedCell {
+                sheet: name.to_owned(),
+                row: line,
+                column: width + extra + 1,
+            });
+        }
+        cells.truncate(width);
+
+        // названия вариантов -> номера, как в PRM
+        for (cell, column) in cells.iter_mut().zip(T::COLUMNS) {
+            if let CellKind::Enum(variants) = column.kind {
+                let index = variants
+                    .iter()
+                    .position(|variant| variant.eq_ignore_ascii_case(cell.trim()));
+                if let Some(index) = index {
+                    *cell = index.to_string();
+                }
+            }
+        }
+        records.push((line, cells));
+    }
+
+    Ok(parse_cells(name, records, errors))
+}
+
+/// Разбирает таблицу за один проход, собирая ошибки всех неверных строк;
+/// `None`, если ошибки есть
+fn parse_cells<T: CsvTable>(
+    sheet: &str,
+    records: Vec<(usize, Vec<String>)>,
+    errors: &mut Vec<PrmParseError>,
+) -> Option<T> {
+    let rows = csv_rows(
+        sheet,
+        records
+            .iter()
+            .map(|(line, cells)| (*line, cells.iter().map(String::as_str).collect())),
+    );
+
+    let recovered = T::from_csv_rows_recovering(&rows);
+    if recovered.is_clean() {
+        Some(recovered.table)
+    } else {
+        errors.extend(recovered.diagnostics);
+        None
+    }
+}
@@ -0,0 +1,62 @@
+use std::borrow::Cow;
+
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+Fostral 2048 16384
+\"Glorx Spot\" 2048 8192
+";
+
+/// Строка лежит внутри `text`, а не скопирована из него
+fn points_into(s: &str, text: &str) -> bool {
+    let range = text.as_bytes().as_ptr_range();
+    range.contains(&s.as_ptr())
+}
+
+#[test]
+fn tokens_borrow_the_input() {
+    let rows = Rows::tokenize("worlds.prm", WORLDS).unwrap();
+
+    for row in rows.iter() {
+        for token in row.tokens() {
+            assert!(matches!(token.text, Cow::Borrowed(_)));
+            assert!(points_into(&token.text, WORLDS));
+        }
+    }
+}
+
+#[test]
+fn borrowed_table_points_into_the_input() {
+    let rows = TableWorld::check_signature(Rows::tokenize("worlds.prm", WORLDS).unwrap()).unwrap();
+    let table = TableWorld::<&str>::parse_rows(&rows).unwrap();
+
+    let names: Vec<_> = table.worlds().iter().map(|w| w.name).collect();
+    assert_eq!(names, ["Fostral", "Glorx Spot"]);
+    for world in table.worlds() {
+        assert!(points_into(world.name, WORLDS));
+    }
+}
+
+#[test]
+fn borrowed_and_owned_tables_agree() {
+    let text = include_str!("fixtures/bunches.prm");
+    let rows = TableBunch::check_signature(Rows::tokenize("bunches.prm", text).unwrap()).unwrap();
+
+    let borrowed = TableBunch::<&str>::parse_rows(&rows).unwrap();
+    let owned = TableBunch::parse_str(text).unwrap();
+
+    assert_eq!(borrowed.bunches.len(), owned.bunches.len());
+    for (b, o) in borrowed.bunches.iter().zip(&owned.bunches) {
+        assert_eq!(b.escave_name, o.escave_name);
+        assert!(points_into(b.escave_name, text));
+        assert_eq!(b.cycles(), o.cycles());
+    }
+
+    // строки, прочитанные из потока, владеют текстом и переживают его
+    let rows = {
+        let buffer = text.to_owned();
+        Rows::tokenize("bunches.prm", &buffer).unwrap().into_owned()
+    };
+    let rows = TableBunch::check_signature(rows).unwrap();
+    assert_eq!(rows.iter().next().unwrap().tokens()[0].value(), "Podish");
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "cache")]
+
+use std::{collections::HashMap, fs, path::Path};
+
+use vangers_prm::*;
+
+fn worlds(width: u32) -> HashMap<String, Vec<u8>> {
+    let mut files = HashMap::new();
+    files.insert(
+        "worlds.prm".to_owned(),
+        format!("uniVang-ParametersFile_Ver_1\nFostral {} 16384\n", width).into_bytes(),
+    );
+    files
+}
+
+/// Имена файлов папки кэша
+fn entries(path: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn second_load_reads_the_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ResourceCache::new(dir.path());
+
+    let set = cache.load(&worlds(2048)).unwrap();
+    assert_eq!(set.worlds.unwrap().worlds()[0].width, 2048);
+
+    // временных файлов не остаётся
+    let names = entries(dir.path());
+    assert_eq!(names.len(), 1);
+    assert!(names[0].ends_with(".prmcache"), "{:?}", names);
+
+    let set = cache.load(&worlds(2048)).unwrap();
+    assert_eq!(set.worlds.unwrap().worlds()[0].width, 2048);
+    assert_eq!(entries(dir.path()), names);
+}
+
+#[test]
+fn least_recently_used_entries_are_pruned() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ResourceCache::new(dir.path()).with_max_entries(2);
+    assert_eq!(cache.max_entries(), 2);
+
+    cache.load(&worlds(1)).unwrap();
+    let first = entries(dir.path());
+    cache.load(&worlds(2)).unwrap();
+    assert_eq!(entries(dir.path()).len(), 2);
+
+    // время изменения первой записи — раньше второй
+    let old = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+    let path = dir.path().join(&first[0]);
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+
+    cache.load(&worlds(3)).unwrap();
+    let names = entries(dir.path());
+    assert_eq!(names.len(), 2);
+    assert!(!names.contains(&first[0]), "{:?}", names);
+}
+
+#[test]
+fn clear_removes_every_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ResourceCache::new(dir.path());
+    cache.load(&worlds(1)).unwrap();
+    cache.load(&worlds(2)).unwrap();
+
+    cache.clear().unwrap();
+    assert!(entries(dir.path()).is_empty());
+}
+
+#[test]
+fn files_are_read_within_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut files = HashMap::new();
+    files.insert(
+        "worlds.prm".to_owned(),
+        vec![b' '; Limits::default().max_file_size + 1],
+    );
+
+    match ResourceCache::new(dir.path()).load(&files) {
+        Err(CacheError::Parse(e)) => {
+            assert!(matches!(
+                e.kind,
+                PrmErrorKind::LimitExceeded(LimitExceeded::FileSize(_))
+            ));
+            assert_eq!(e.span.file, "worlds.prm");
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("file over the limit is read"),
+    }
+}
@@ -0,0 +1,165 @@
+#![cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+
+use std::collections::{BTreeMap, HashMap};
+
+use vangers_prm::*;
+
+const PRICES: &str = "uniVang-ParametersFile_Ver_1
+Podish
+Nymbos 10 5
+Incubator
+Phlegma 30 15
+";
+
+const VANGERS: &str = "uniVang-ParametersFile_Ver_1\r
+// всего ванджеров\r
+40   \r
+Fostral 10 /* первый мир */\r
+Glorx\t5\r
+";
+
+/// Файлы набора: блоки эскейвов нарочно не по алфавиту, с комментариями,
+/// CRLF и пробелами в конце строк
+fn files() -> HashMap<String, Vec<u8>> {
+    let mut files = HashMap::new();
+    let mut add = |name: &str, bytes: &[u8]| {
+        files.insert(name.to_owned(), bytes.to_vec());
+    };
+    add("bunches.prm", include_bytes!("fixtures/bunches.prm"));
+    add("escaves.prm", include_bytes!("fixtures/escaves.prm"));
+    add("spot.prm", include_bytes!("fixtures/spot.prm"));
+    add("passages.prm", include_bytes!("fixtures/passages.prm"));
+    add("price.prm", PRICES.as_bytes());
+    add("vangers.prm", VANGERS.as_bytes());
+    files
+}
+
+/// Тексты файлов по именам: в отчёте о несовпадении видна разница
+fn texts(files: &HashMap<String, Vec<u8>>) -> BTreeMap<&str, String> {
+    files
+        .iter()
+        .map(|(name, bytes)| (name.as_str(), String::from_utf8_lossy(bytes).into_owned()))
+        .collect()
+}
+
+/// Набор без сохранённого форматирования: каждый файл в каноническом виде
+fn canonical(files: &HashMap<String, Vec<u8>>) -> HashMap<String, Vec<u8>> {
+    ResourceSet::load(files).unwrap().to_files().unwrap()
+}
+
+fn assert_round_trip<F, G>(to: F, from: G)
+where
+    F: Fn(&ResourceSet) -> Result<String, ConvertError>,
+    G: Fn(&str) -> Result<ResourceSet, ConvertError>,
+{
+    let files = files();
+    let mut set = ResourceSet::load(&files).unwrap();
+    set.load_layout(&files).unwrap();
+    assert_eq!(texts(&set.to_files().unwrap()), texts(&files));
+
+    // исходные файлы в документ не попадают
+    let text = to(&set).unwrap();
+    assert!(!text.contains("первый мир"), "{}", text);
+    let mut again = from(&text).unwrap();
+    assert!(again.documents.is_empty());
+    assert_eq!(texts(&again.to_files().unwrap()), texts(&canonical(&files)));
+
+    // форматирование загружается из исходной папки заново
+    again.load_layout(&files).unwrap();
+    assert_eq!(texts(&again.to_files().unwrap()), texts(&files));
+
+    // порядок блоков не меняется
+    let bunches: Vec<_> = again
+        .bunches
+        .as_ref()
+        .unwrap()
+        .bunches
+        .iter()
+        .map(|b| b.escave_name.as_str())
+        .collect();
+    assert_eq!(bunches, ["Podish", "Incubator", "Lampasso"]);
+    let prices: Vec<_> = again.prices.as_ref().unwrap().prices.keys().collect();
+    assert_eq!(prices, ["Podish", "Incubator"]);
+}
+
+#[test]
+fn layout_is_kept_only_on_request() {
+    let files = files();
+    let set = ResourceSet::load(&files).unwrap();
+    assert!(set.documents.is_empty());
+    assert_eq!(
+        set.to_files().unwrap()["vangers.prm"],
+        b"uniVang-ParametersFile_Ver_1\n40\nFostral 10\nGlorx 5\n".to_vec()
+    );
+}
+
+#[test]
+fn edited_value_changes_only_its_token() {
+    let files = files();
+    let mut set = ResourceSet::load(&files).unwrap();
+    set.load_layout(&files).unwrap();
+    set.vangers.as_mut().unwrap().weights["Fostral"] = 12;
+
+    let files = set.to_files().unwrap();
+    assert_eq!(
+        files["vangers.prm"],
+        VANGERS.replace("Fostral 10", "Fostral 12").into_bytes()
+    );
+}
+
+#[test]
+fn layout_that_does_not_fit_is_an_error() {
+    let files = files();
+    let mut set = ResourceSet::load(&files).unwrap();
+
+    // форматирование от файла, который не разбирается в таблицу
+    let mut layout = files.clone();
+    let broken = VANGERS.replace("Glorx\t5", "Glorx\tfive");
+    layout.insert("vangers.prm".to_owned(), broken.into_bytes());
+    set.load_layout(&layout).unwrap();
+
+    let e = set.to_files().err().unwrap();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert!(e.to_string().starts_with("`vangers.prm`"), "{}", e);
+
+    // без документа таблица записывается в каноническом виде
+    set.documents.shift_remove("vangers.prm");
+    let written = set.to_files().unwrap();
+    assert_eq!(written["vangers.prm"], canonical(&files)["vangers.prm"]);
+    assert_eq!(written["price.prm"], files["price.prm"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip_keeps_the_values() {
+    assert_round_trip(ResourceSet::to_json, ResourceSet::from_json);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_round_trip_keeps_the_values() {
+    assert_round_trip(ResourceSet::to_toml, ResourceSet::from_toml);
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn ron_round_trip_keeps_the_values() {
+    assert_round_trip(ResourceSet::to_ron, ResourceSet::from_ron);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn errors_point_at_the_structured_path() {
+    let set = ResourceSet::load(&files()).unwrap();
+    let text = set
+        .to_json()
+        .unwrap()
+        .replacen("\"cirt\": 800", "\"cirt\": -1", 1);
+
+    match ResourceSet::from_json(&text).err().unwrap() {
+        ConvertError::Deserialize { path, .. } => {
+            assert_eq!(path, "bunches.bunches[1].cults[0].stage.cirt")
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+}
@@ -0,0 +1,103 @@
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1\r
+// миры Цепи   \r
+Fostral   2048  16384   // первый мир\r
+\tGlorx     2048  16384 \r
+/* ещё\r
+   не готов */ Necross 2048 16384\r
+\r
+Xplo 2048 4096";
+
+fn world<'t>(table: &'t mut TableWorld, name: &str) -> &'t mut World {
+    table
+        .worlds_mut()
+        .iter_mut()
+        .find(|w| w.name == name)
+        .unwrap()
+}
+
+#[test]
+fn parse_and_write_is_byte_identical() {
+    let document = PrmDocument::parse(WORLDS);
+    assert_eq!(document.to_string(), WORLDS);
+    assert_eq!(document.to_bytes().unwrap(), WORLDS.as_bytes());
+
+    let lf = WORLDS.replace("\r\n", "\n") + "\n";
+    assert_eq!(PrmDocument::parse(&lf).to_string(), lf);
+}
+
+#[test]
+fn keeps_bom_and_encoding_on_write() {
+    let mut bytes = b"\xEF\xBB\xBF".to_vec();
+    bytes.extend_from_slice(WORLDS.as_bytes());
+
+    let recovered = PrmDocument::decode("worlds.prm", &bytes, None);
+    assert!(recovered.is_clean());
+    assert_eq!(recovered.table.to_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn tables_read_the_document() {
+    let document = PrmDocument::parse(WORLDS);
+    let table = TableWorld::parse_document(&document).unwrap();
+
+    let names: Vec<_> = table.worlds().iter().map(|w| w.name.as_str()).collect();
+    assert_eq!(names, ["Fostral", "Glorx", "Necross", "Xplo"]);
+}
+
+#[test]
+fn update_changes_only_the_target_token() {
+    let mut document = PrmDocument::parse(WORLDS);
+    let mut table = TableWorld::parse_document(&document).unwrap();
+    world(&mut table, "Glorx").height = 8192;
+
+    document.update(&table).unwrap();
+    assert_eq!(
+        document.to_string(),
+        WORLDS.replace("\tGlorx     2048  16384 ", "\tGlorx     2048  8192 ")
+    );
+
+    // таблица без изменений документ не трогает
+    let unchanged = TableWorld::parse_document(&document).unwrap();
+    let before = document.to_string();
+    document.update(&unchanged).unwrap();
+    assert_eq!(document.to_string(), before);
+}
+
+#[test]
+fn update_inserts_and_removes_rows() {
+    let mut document = PrmDocument::parse(WORLDS);
+    let table = TableWorld::parse_str(&format!("{}\nBoozeena 1024 1024\n", WORLDS)).unwrap();
+
+    document.update(&table).unwrap();
+    assert_eq!(
+        document.to_string(),
+        format!("{}\r\nBoozeena 1024 1024", WORLDS)
+    );
+
+    // у удалённого мира остаётся строка с комментарием
+    let mut table = TableWorld::parse_document(&document).unwrap();
+    table.worlds_mut().retain(|w| w.name != "Fostral");
+    document.update(&table).unwrap();
+    assert!(document.to_string().contains("\r\n// первый мир\r\n"));
+    assert!(!document.to_string().contains("Fostral"));
+    assert_eq!(
+        TableWorld::parse_document(&document)
+            .unwrap()
+            .worlds()
+            .len(),
+        4
+    );
+}
+
+#[test]
+fn update_rejects_a_changed_layout() {
+    let mut document = PrmDocument::parse(WORLDS);
+    let table = TableWorld::parse_document(&document).unwrap();
+
+    // документ правили после разбора таблицы
+    let mut edited = PrmDocument::parse(&WORLDS.replace("Xplo 2048 4096", "Xplo 2048 4096 1"));
+    assert!(edited.update(&table).is_err());
+    assert!(document.update(&table).is_ok());
+}
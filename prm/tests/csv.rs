@@ -0,0 +1,170 @@
+#![cfg(feature = "csv")]
+
+use vangers_prm::*;
+
+const CAR: &str = "uniVang-ParametersFile_Ver_1
+1
+1
+0
+Oxidize 1 100 50 1 1 1 0 10 20 30 1 1 1 0 0 0 0 5 0
+Raffa 0 10 5 1 0 0 0 13 23 33 1 1 1 0 0 0 0 1 0
+";
+
+const ITEMS: &str = "uniVang-ParametersFile_Ver_1
+2
+Nymbos 1 0 0 1 1 0 0
+\"Eleepod Bath\" 2 -1 3 4 5 6 7
+";
+
+/// PRM -> CSV -> PRM не меняет канонический текст таблицы
+fn assert_round_trip<T: PrmFile + PrmWrite + CsvTable>(text: &str) {
+    let table = T::parse_str(text).unwrap();
+    let csv = table.to_csv_string().unwrap();
+    let again = T::from_csv_str("table.csv", &csv).unwrap();
+    assert_eq!(again.to_prm_string(), table.to_prm_string(), "{}", csv);
+}
+
+fn parse_error<T: CsvTable>(csv: &str) -> PrmParseError {
+    match T::from_csv_str("table.csv", csv) {
+        Err(CsvError::Parse(e)) => e,
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("CSV is accepted"),
+    }
+}
+
+#[test]
+fn every_table_round_trips() {
+    assert_round_trip::<TableMechos>(CAR);
+    assert_round_trip::<TableItem>(ITEMS);
+    assert_round_trip::<TableBunch>(include_str!("fixtures/bunches.prm"));
+    assert_round_trip::<TableEscave>(include_str!("fixtures/escaves.prm"));
+    assert_round_trip::<TableSpot>(include_str!("fixtures/spot.prm"));
+    assert_round_trip::<TablePassage>(include_str!("fixtures/passages.prm"));
+    assert_round_trip::<TableWorld>("uniVang-ParametersFile_Ver_1\nFostral 2048 16384\n");
+    assert_round_trip::<TablePrice>(
+        "uniVang-ParametersFile_Ver_1\nPodish\nNymbos 10 5\nIncubator\nPhlegma 30 15\n",
+    );
+    assert_round_trip::<TableVangersWeight>(
+        "uniVang-ParametersFile_Ver_1\n40\nFostral 10\nGlorx 5\n",
+    );
+}
+
+#[test]
+fn price_is_written_in_long_format() {
+    let table = TablePrice::parse_str(
+        "uniVang-ParametersFile_Ver_1\nPodish\nNymbos 10 5\n\"Eleepod Bath\" 20 10\n",
+    )
+    .unwrap();
+    assert_eq!(
+        table.to_csv_string().unwrap(),
+        "escave,item,buy,sell\nPodish,Nymbos,10,5\nPodish,Eleepod Bath,20,10\n"
+    );
+}
+
+#[test]
+fn type_errors_point_at_csv_row_and_column() {
+    let csv = TableItem::parse_str(ITEMS)
+        .unwrap()
+        .to_csv_string()
+        .unwrap()
+        .replace("Nymbos,1,0,0,1,1,0,0", "Nymbos,1,0,0,1,x,0,0");
+    let e = parse_error::<TableItem>(&csv);
+
+    assert!(matches!(e.kind, PrmErrorKind::Item(ItemParseError::Count)));
+    assert_eq!(e.span.file, "table.csv");
+    assert_eq!(e.span.line, 2);
+    assert_eq!(e.span.column, 6);
+    assert_eq!(e.span.token, "x");
+}
+
+#[test]
+fn header_must_match_columns() {
+    let e = TableWorld::from_csv_str("worlds.csv", "name,height,width\n")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        e,
+        CsvError::Header {
+            column: 2,
+            expected: "width",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn bunch_rows_must_agree_on_bios() {
+    let csv = TableBunch::parse_str(include_str!("fixtures/bunches.prm"))
+        .unwrap()
+        .to_csv_string()
+        .unwrap()
+        .replace("Podish,0,Waace Ticks", "Podish,2,Waace Ticks");
+    let e = parse_error::<TableBunch>(&csv);
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Bunch(BunchParseError::BiosMismatch)
+    ));
+    assert_eq!((e.span.line, e.span.column), (3, 2));
+}
+
+#[test]
+fn bunch_rows_must_be_contiguous() {
+    let csv = "escave,bios,stage,cirt,time,price,palette,game,source,goods_begin,goods_begin_count,destination,goods_end,goods_end_count,rotten_goods
+Podish,0,Eleerection,1000,60,100,resource/pal/cycle0.pal,none,,,,,,,
+Lampasso,2,Kiss,1200,50,110,resource/pal/cycle4.pal,none,,,,,,,
+Podish,0,Ebb,900,35,95,resource/pal/cycle3.pal,none,,,,,,,
+";
+    let e = parse_error::<TableBunch>(csv);
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Bunch(BunchParseError::SplitBunch)
+    ));
+    assert_eq!((e.span.line, e.span.column), (4, 1));
+}
+
+#[test]
+fn location_rows_must_repeat_the_title() {
+    let csv = TableEscave::parse_str(include_str!("fixtures/escaves.prm"))
+        .unwrap()
+        .to_csv_string()
+        .unwrap()
+        .replacen(
+            "Podish,Fostral,1100,1500,none,Nymbos,Lampasso",
+            "Podish,Fostral,1100,1600,none,Nymbos,Lampasso",
+            1,
+        );
+    let e = parse_error::<TableEscave>(&csv);
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Location(LocationParseError::TitleMismatch)
+    ));
+    assert_eq!((e.span.line, e.span.column), (3, 4));
+    assert_eq!(e.span.token, "1600");
+
+    let csv = "name,world,pos_x,pos_y,personal_item,goods_item,goods_destination
+Podish,Fostral,1100,1500,none,Nymbos,Incubator
+Ogorod,Glorx,700,12000,none,,
+Podish,Fostral,1100,1500,none,Phlegma,Ogorod
+";
+    let e = parse_error::<TableEscave>(csv);
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Location(LocationParseError::SplitBlock)
+    ));
+    assert_eq!(e.span.line, 4);
+}
+
+#[test]
+fn vangers_world_is_not_repeated() {
+    let csv = "vangers_total,world,weight\n40,Fostral,10\n,Glorx,5\n,Fostral,7\n";
+    let e = parse_error::<TableVangersWeight>(csv);
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::VangersWeight(VangersWeightParseError::DuplicateWorld)
+    ));
+    assert_eq!((e.span.line, e.span.column), (4, 2));
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+// миры Цепи
+Фострал 2048 16384
+Глоркс 2048 16384
+";
+
+fn names(table: &TableWorld) -> Vec<&str> {
+    table.worlds().iter().map(|w| w.name.as_str()).collect()
+}
+
+#[test]
+fn detects_dos_and_windows_cyrillic() {
+    let cp866 = Encoding::Cp866.encode(WORLDS).unwrap();
+    let cp1251 = Encoding::Cp1251.encode(WORLDS).unwrap();
+
+    assert_eq!(Encoding::detect(WORLDS.as_bytes()), Encoding::Utf8);
+    assert_eq!(Encoding::detect(&cp866), Encoding::Cp866);
+    assert_eq!(Encoding::detect(&cp1251), Encoding::Cp1251);
+
+    for bytes in [&cp866[..], &cp1251[..], WORLDS.as_bytes()] {
+        let table = TableWorld::parse_bytes(bytes).unwrap();
+        assert_eq!(names(&table), ["Фострал", "Глоркс"]);
+    }
+}
+
+#[test]
+fn explicit_encoding_wins_over_detection() {
+    let cp866 = Encoding::Cp866.encode(WORLDS).unwrap();
+    let table = TableWorld::parse_reader_encoded(&cp866[..], Encoding::Cp866).unwrap();
+    assert_eq!(names(&table), ["Фострал", "Глоркс"]);
+}
+
+#[test]
+fn invalid_bytes_are_a_diagnostic_not_a_missing_row() {
+    let mut bytes = b"uniVang-ParametersFile_Ver_1\nFostral 2048 16384\n".to_vec();
+    bytes.extend_from_slice(b"Gl\xFForx 2048 16384\n");
+
+    let e = TableWorld::parse_reader_encoded(&bytes[..], Encoding::Utf8)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Lex(LexError::InvalidEncoding(Encoding::Utf8))
+    ));
+    assert_eq!(e.span.line, 3);
+    assert_eq!(e.span.column, 3);
+
+    let recovered = TableWorld::parse_str_recovering(&String::from_utf8_lossy(&bytes)).unwrap();
+    assert_eq!(recovered.table.worlds().len(), 2);
+}
+
+#[test]
+fn writes_back_in_the_same_encoding() {
+    let cp1251 = Encoding::Cp1251.encode(WORLDS).unwrap().into_owned();
+    let mut files = HashMap::new();
+    files.insert("worlds.prm".to_owned(), cp1251);
+
+    let set = ResourceSet::load(&files).unwrap();
+    assert_eq!(set.encoding, Encoding::Cp1251);
+
+    let written = &set.to_files().unwrap()["worlds.prm"];
+    assert_eq!(Encoding::detect(written), Encoding::Cp1251);
+    let table = TableWorld::parse_bytes(written).unwrap();
+    assert_eq!(names(&table), ["Фострал", "Глоркс"]);
+
+    let mut utf8 = vec![];
+    table.write_encoded(&mut utf8, Encoding::Utf8).unwrap();
+    assert!(std::str::from_utf8(&utf8)
+        .unwrap()
+        .contains("Фострал 2048 16384"));
+}
+
+#[test]
+fn characters_missing_in_the_encoding_are_an_error() {
+    let e = Encoding::Cp866.encode("Fostral ✓").err().unwrap();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,113 @@
+use vangers_prm::*;
+
+const ESCAVES: &str = include_str!("fixtures/escaves.prm");
+
+fn goods(escave: &Escave) -> Vec<(&str, &str)> {
+    escave
+        .goods
+        .iter()
+        .map(|(item, dest)| (item.as_str(), dest.as_str()))
+        .collect()
+}
+
+#[test]
+fn reads_goods_of_every_escave() {
+    let table = TableEscave::parse_str(ESCAVES).unwrap();
+
+    let names: Vec<_> = table.escaves.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Podish", "Incubator", "Lampasso", "Ogorod"]);
+
+    let podish = &table.escaves[0];
+    assert_eq!((podish.pos_x, podish.pos_y), (1100, 1500));
+    assert_eq!(podish.personal_item_name, None);
+    assert_eq!(
+        goods(podish),
+        [
+            ("Nymbos", "Incubator"),
+            ("Nymbos", "Lampasso"),
+            ("Phlegma", "Ogorod")
+        ]
+    );
+
+    assert_eq!(
+        goods(&table.escaves[1]),
+        [("Heroin", "Podish"), ("Shrub", "Lampasso")]
+    );
+
+    let lampasso = &table.escaves[2];
+    assert_eq!(lampasso.world_name, "Glorx");
+    assert_eq!(lampasso.personal_item_name.as_deref(), Some("Eleepod Bath"));
+    assert_eq!(
+        goods(lampasso),
+        [
+            ("Toxick", "Ogorod"),
+            ("Poponka", "Podish"),
+            ("Sweetle", "B-Zone")
+        ]
+    );
+
+    assert!(table.escaves[3].goods.is_empty());
+}
+
+#[test]
+fn writes_back_goods_blocks() {
+    let table = TableEscave::parse_str(ESCAVES).unwrap();
+    let text = table.to_prm_string();
+
+    let again = TableEscave::parse_str(&text).unwrap();
+    assert_eq!(again.to_prm_string(), text);
+    assert_eq!(again.escaves.len(), 4);
+    assert_eq!(goods(&again.escaves[2]), goods(&table.escaves[2]));
+}
+
+#[test]
+fn block_without_none_is_an_error() {
+    // у Incubator нет завершающей строки: ошибка указывает на заголовок Lampasso
+    let text = ESCAVES.replacen("Shrub Lampasso\nnone\n", "Shrub Lampasso\n", 1);
+    let e = TableEscave::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Location(LocationParseError::ExpectedTerminateLine)
+    ));
+    assert_eq!(e.span.line, 11);
+    assert_eq!(e.span.token, "Lampasso");
+
+    // следующий блок при этом разбирается
+    let recovered = TableEscave::parse_str_recovering(&text).unwrap();
+    assert_eq!(recovered.diagnostics.len(), 1);
+    let names: Vec<_> = recovered
+        .table
+        .escaves
+        .iter()
+        .map(|e| e.name.as_str())
+        .collect();
+    assert_eq!(names, ["Podish", "Lampasso", "Ogorod"]);
+}
+
+#[test]
+fn missing_none_at_end_of_file_is_an_error() {
+    let text = ESCAVES.trim_end().trim_end_matches("none");
+    let e = TableEscave::parse_str(text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Location(LocationParseError::ExpectedTerminateLine)
+    ));
+}
+
+#[test]
+fn wrong_goods_line_is_an_error() {
+    let text = ESCAVES.replace("Heroin Podish", "Heroin Podish Lampasso");
+    let e = TableEscave::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Location(LocationParseError::UnexpectedAdditionalParameterAtGoodsLine)
+    ));
+    assert_eq!(e.span.line, 9);
+
+    let recovered = TableEscave::parse_str_recovering(&text).unwrap();
+    assert_eq!(recovered.diagnostics.len(), 1);
+    assert_eq!(recovered.table.escaves.len(), 3);
+}
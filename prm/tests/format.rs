@@ -0,0 +1,91 @@
+use vangers_prm::*;
+
+#[test]
+fn aligns_rows_of_one_block() {
+    let text = "uniVang-ParametersFile_Ver_1  \r
+2\r
+\r
+\r
+Nymbos 1 0 0 1 1 0 0 // пустой\r
+\"Eleepod Bath\"   12 -1 3 4 5 6 7\r
+";
+    let document = PrmDocument::parse(text).formatted();
+
+    assert_eq!(
+        document.to_string(),
+        "uniVang-ParametersFile_Ver_1\r
+2\r
+\r
+Nymbos          1  0 0 1 1 0 0 // пустой\r
+\"Eleepod Bath\" 12 -1 3 4 5 6 7\r
+"
+    );
+    // отформатированный документ разбирается в ту же таблицу
+    let table = TableItem::parse_str(&document.to_string()).unwrap();
+    assert_eq!(table.items[1].name, "Eleepod Bath");
+}
+
+#[test]
+fn blocks_are_aligned_separately() {
+    let text = "uniVang-ParametersFile_Ver_1
+Podish Fostral 1100 1500 none
+Nymbos Incubator
+Phlegma Ogorod
+none
+Lampasso Glorx 1500 2000 Heroin
+\"Eleepod Bath\" Podish
+none
+";
+    let document = PrmDocument::parse(text).formatted();
+
+    // товары второго блока не раздвигают товары первого
+    assert_eq!(
+        document.to_string(),
+        "uniVang-ParametersFile_Ver_1
+Podish Fostral 1100 1500 none
+Nymbos  Incubator
+Phlegma Ogorod
+none
+Lampasso Glorx 1500 2000 Heroin
+\"Eleepod Bath\" Podish
+none
+"
+    );
+}
+
+#[test]
+fn rows_of_different_kinds_are_not_aligned_together() {
+    // строка цикла и строка игры: по пять слов, но разного вида
+    let text = "uniVang-ParametersFile_Ver_1
+Podish 0 1
+Eleerection 1000 60 100 resource/pal/cycle0.pal
+HARVEST Nymbos 20 Incubator Rotten
+";
+    let document = PrmDocument::parse(text);
+    assert!(document.is_formatted());
+    assert_eq!(document.formatted().to_string(), text);
+}
+
+#[test]
+fn check_mode_reports_first_unformatted_line() {
+    let text = "uniVang-ParametersFile_Ver_1
+// комментарий сохраняется
+Fostral 2048 16384
+Glorx  2048 8192
+";
+    let document = PrmDocument::parse(text);
+    assert!(!document.is_formatted());
+    assert_eq!(document.unformatted_line(), Some(4));
+
+    let formatted = document.formatted();
+    assert!(formatted.is_formatted());
+    assert_eq!(formatted.unformatted_line(), None);
+    assert_eq!(
+        formatted.to_string(),
+        "uniVang-ParametersFile_Ver_1
+// комментарий сохраняется
+Fostral 2048 16384
+Glorx   2048  8192
+"
+    );
+}
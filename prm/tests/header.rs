@@ -0,0 +1,72 @@
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+Fostral 2048 16384
+";
+
+#[test]
+fn parses_and_prints_signature() {
+    let header = PrmHeader::parse("uniVang-ParametersFile_Ver_1").unwrap();
+    assert_eq!(header, PrmHeader::default());
+    assert!(header.is_univang());
+    assert_eq!(header.to_string(), "uniVang-ParametersFile_Ver_1");
+
+    let header = PrmHeader::parse("Other_Ver_12").unwrap();
+    assert_eq!(header.family, "Other");
+    assert_eq!(header.version, 12);
+    assert!(!header.is_univang());
+
+    assert!(PrmHeader::parse("uniVang-ParametersFile").is_none());
+    assert!(PrmHeader::parse("_Ver_1").is_none());
+    assert!(PrmHeader::parse("uniVang-ParametersFile_Ver_x").is_none());
+}
+
+#[test]
+fn signature_is_kept_in_rows() {
+    let rows = TableWorld::read_rows(WORLDS.as_bytes()).unwrap();
+    assert_eq!(rows.header(), Some(&PrmHeader::default()));
+    // сигнатура отброшена, остались только строки таблицы
+    assert_eq!(rows.iter().count(), 1);
+
+    let document = PrmDocument::parse(WORLDS);
+    assert_eq!(document.header(), Some(PrmHeader::default()));
+}
+
+#[test]
+fn wrong_signature_reports_what_was_found() {
+    let text = WORLDS.replace("uniVang-ParametersFile_Ver_1", "Vangers params");
+    let e = TableWorld::parse_str(&text).err().unwrap();
+
+    match &e.kind {
+        PrmErrorKind::OpenFile(PrmOpenError::WrongSignature(found)) => {
+            assert_eq!(found, "Vangers params")
+        }
+        kind => panic!("unexpected error: {}", kind),
+    }
+    assert_eq!(e.span.line, 1);
+    assert!(e.to_string().contains("`Vangers params`"));
+}
+
+#[test]
+fn unsupported_version_is_an_error() {
+    let text = WORLDS.replace("_Ver_1", "_Ver_2");
+    let e = TableWorld::parse_str(&text).err().unwrap();
+
+    match &e.kind {
+        PrmErrorKind::OpenFile(PrmOpenError::UnsupportedVersion(header)) => {
+            assert_eq!(*header, PrmHeader::univang(2))
+        }
+        kind => panic!("unexpected error: {}", kind),
+    }
+}
+
+#[test]
+fn empty_file_has_no_signature() {
+    let e = TableWorld::parse_str("// только комментарий\n")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::OpenFile(PrmOpenError::MissingSignature)
+    ));
+}
@@ -0,0 +1,79 @@
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+\"Glorx // Spot\" 2048 16384 /* ширина */ /* высота */
+/* многострочный
+   комментарий */ Fostral 2048 /* между */ 16384 // хвост
+Necross 2048 16384 /* до конца
+   строки */
+";
+
+fn names(table: &TableWorld) -> Vec<&str> {
+    table.worlds().iter().map(|w| w.name.as_str()).collect()
+}
+
+#[test]
+fn quotes_and_comments_are_skipped() {
+    let table = TableWorld::parse_str(WORLDS).unwrap();
+
+    // `//` внутри кавычек — часть слова, а не комментарий
+    assert_eq!(names(&table), ["Glorx // Spot", "Fostral", "Necross"]);
+    assert_eq!(table.worlds()[1].height, 16384);
+}
+
+#[test]
+fn tokens_keep_quotes_and_columns() {
+    let rows = Rows::tokenize("worlds.prm", WORLDS).unwrap();
+    let row = rows.iter().nth(1).unwrap();
+
+    assert_eq!(row.line(), 2);
+    let first = &row.tokens()[0];
+    assert_eq!(first.text, "\"Glorx // Spot\"");
+    assert_eq!(first.value(), "Glorx // Spot");
+    assert_eq!(first.column, 1);
+    assert_eq!(row.tokens()[1].column, 17);
+
+    // слово после закрытия многострочного комментария
+    let row = rows.iter().nth(2).unwrap();
+    assert_eq!(row.line(), 4);
+    assert_eq!(row.tokens()[0].value(), "Fostral");
+    assert_eq!(row.tokens()[0].column, 19);
+    assert_eq!(row.len(), 3);
+}
+
+#[test]
+fn unterminated_comment_is_an_error() {
+    let text = WORLDS.replace("/* до конца\n   строки */", "/* до конца\n");
+    let e = TableWorld::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Lex(LexError::UnterminatedComment)
+    ));
+    assert_eq!(e.span.line, 5);
+}
+
+#[test]
+fn unterminated_string_is_an_error() {
+    let text = WORLDS.replace("\"Glorx // Spot\"", "\"Glorx // Spot");
+    let e = TableWorld::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Lex(LexError::UnterminatedString)
+    ));
+    assert_eq!(e.span.line, 2);
+    assert_eq!(e.span.column, 1);
+}
+
+#[test]
+fn quoted_cycle_names_may_contain_spaces() {
+    let table = TableBunch::parse_str(include_str!("fixtures/bunches.prm")).unwrap();
+
+    let stages: Vec<_> = table.bunches[0]
+        .cults
+        .iter()
+        .map(|c| c.stage().name.as_str())
+        .collect();
+    assert_eq!(stages, ["Eleerection", "Waace Ticks"]);
+}
@@ -0,0 +1,102 @@
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+Fostral 2048 16384
+Glorx 2048 8192
+Necross 2048 16384
+";
+
+fn limit(e: &PrmParseError) -> &LimitExceeded {
+    match &e.kind {
+        PrmErrorKind::LimitExceeded(limit) => limit,
+        kind => panic!("unexpected error: {}", kind),
+    }
+}
+
+#[test]
+fn file_size_is_limited() {
+    let limits = Limits {
+        max_file_size: 32,
+        ..Limits::default()
+    };
+    let e = TableWorld::read_rows_limited(WORLDS.as_bytes(), None, limits)
+        .err()
+        .unwrap();
+    assert!(matches!(limit(&e), LimitExceeded::FileSize(32)));
+    assert_eq!(e.span.file, "worlds.prm");
+
+    let e = Rows::tokenize_limited("worlds.prm", WORLDS, limits)
+        .err()
+        .unwrap();
+    assert!(matches!(limit(&e), LimitExceeded::FileSize(32)));
+}
+
+#[test]
+fn line_length_is_limited() {
+    let limits = Limits {
+        max_line_length: 28,
+        ..Limits::default()
+    };
+    let text = WORLDS.replace("Glorx", "Glorx-with-a-very-long-name");
+    let e = TableWorld::read_rows_limited(text.as_bytes(), None, limits)
+        .err()
+        .unwrap();
+    assert!(matches!(limit(&e), LimitExceeded::LineLength(28)));
+    assert_eq!(e.span.line, 3);
+}
+
+#[test]
+fn row_count_is_limited() {
+    let limits = Limits {
+        max_rows: 3,
+        ..Limits::default()
+    };
+    // сигнатура тоже строка с данными
+    let e = TableWorld::read_rows_limited(WORLDS.as_bytes(), None, limits)
+        .err()
+        .unwrap();
+    assert!(matches!(limit(&e), LimitExceeded::Rows(3)));
+    assert_eq!(e.span.line, 4);
+
+    let limits = Limits {
+        max_rows: 4,
+        ..limits
+    };
+    assert!(TableWorld::read_rows_limited(WORLDS.as_bytes(), None, limits).is_ok());
+}
+
+#[test]
+fn cycle_count_is_limited() {
+    let text = include_str!("fixtures/bunches.prm").replace("Podish 0 2", "Podish 0 1000000000");
+    let e = TableBunch::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        limit(&e),
+        LimitExceeded::Cycles {
+            limit: 256,
+            found: 1000000000
+        }
+    ));
+    assert_eq!(e.span.line, 3);
+}
+
+#[test]
+fn huge_item_count_does_not_allocate() {
+    let text = "uniVang-ParametersFile_Ver_1
+18446744073709551615
+Nymbos 1 0 0 1 1 0 0
+";
+    let e = TableItem::parse_str(text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Item(ItemParseError::ExpectedAdditionalItem)
+    ));
+}
+
+#[test]
+fn unlimited_accepts_everything() {
+    let limits = Limits::unlimited();
+    assert_eq!(limits.max_rows, usize::MAX);
+    let rows = TableWorld::read_rows_limited(WORLDS.as_bytes(), None, limits).unwrap();
+    assert_eq!(rows.limits(), &limits);
+}
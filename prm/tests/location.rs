@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use vangers_prm::*;
+
+fn resource_set() -> ResourceSet {
+    let mut files = HashMap::new();
+    files.insert(
+        "escaves.prm".to_owned(),
+        include_bytes!("fixtures/escaves.prm").to_vec(),
+    );
+    files.insert(
+        "spot.prm".to_owned(),
+        include_bytes!("fixtures/spot.prm").to_vec(),
+    );
+    ResourceSet::load(&files).unwrap()
+}
+
+#[test]
+fn finds_escaves_and_spots_by_name() {
+    let set = resource_set();
+    let locations = set.locations();
+
+    let podish = locations.get("Podish").unwrap();
+    assert_eq!(podish.kind, LocationKind::Escave);
+    assert_eq!(podish.goods.len(), 3);
+
+    let zone = locations.get("B-Zone").unwrap();
+    assert_eq!(zone.kind, LocationKind::Spot);
+    assert_eq!(zone.world_name, "Necross");
+
+    assert!(locations.get("Nowhere").is_none());
+}
+
+#[test]
+fn keeps_kinds_apart() {
+    let set = resource_set();
+    let locations = set.locations();
+
+    let escaves: Vec<_> = locations
+        .of_kind(LocationKind::Escave)
+        .map(|l| l.name)
+        .collect();
+    assert_eq!(escaves, ["Podish", "Incubator", "Lampasso", "Ogorod"]);
+
+    let spots: Vec<_> = locations
+        .of_kind(LocationKind::Spot)
+        .map(|l| l.name)
+        .collect();
+    assert_eq!(spots, ["Threall", "B-Zone", "Spobs"]);
+
+    // каждая точка записывается обратно в свой файл
+    let files = set.to_files().unwrap();
+    let again = ResourceSet::load(&files).unwrap();
+    assert_eq!(again.escaves.unwrap().escaves.len(), 4);
+    assert_eq!(again.spots.unwrap().spots.len(), 3);
+}
+
+#[test]
+fn goods_destinations_are_known_locations() {
+    let set = resource_set();
+    let locations = set.locations();
+
+    for location in &locations.locations {
+        for (_, destination) in &location.goods {
+            assert!(locations.contains(destination), "{}", destination);
+        }
+    }
+}
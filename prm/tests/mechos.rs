@@ -0,0 +1,159 @@
+use vangers_prm::*;
+
+const CAR: &str = "uniVang-ParametersFile_Ver_1
+3
+1
+1
+Oxidize 1 100 50 1 1 1 0 10 20 30 1 1 1 0 0 0 0 5 0
+Dead-Eye 2 200 100 2 1 1 0 11 21 31 1 1 1 0 0 0 0 6 0
+Last-Moggy 3 300 150 2 2 1 0 12 22 32 1 1 1 0 0 0 0 7 0
+Raffa 0 10 5 1 0 0 0 13 23 33 1 1 1 0 0 0 0 1 0
+Constructor 5 0 0 3 3 3 3 14 24 34 1 1 1 1 1 1 1 9 1
+";
+
+fn names(mechoses: &[Mechos]) -> Vec<&str> {
+    mechoses.iter().map(|m| m.name.as_str()).collect()
+}
+
+#[test]
+fn reads_counters_as_groups() {
+    let table = TableMechos::parse_str(CAR).unwrap();
+
+    let counters = table.counters();
+    assert_eq!(
+        (counters.main, counters.raffa, counters.constructor),
+        (3, 1, 1)
+    );
+    assert_eq!(counters.total(), Some(5));
+
+    let groups: Vec<_> = table.groups().collect();
+    assert_eq!(
+        groups,
+        [
+            MechosGroup::Main,
+            MechosGroup::Main,
+            MechosGroup::Main,
+            MechosGroup::Raffa,
+            MechosGroup::Constructor
+        ]
+    );
+
+    assert_eq!(
+        names(table.mechoses_of(MechosGroup::Main)),
+        ["Oxidize", "Dead-Eye", "Last-Moggy"]
+    );
+    assert_eq!(names(table.mechoses_of(MechosGroup::Raffa)), ["Raffa"]);
+    assert_eq!(
+        names(table.mechoses_of(MechosGroup::Constructor)),
+        ["Constructor"]
+    );
+}
+
+#[test]
+fn writes_back_the_same_file() {
+    let table = TableMechos::parse_str(CAR).unwrap();
+    assert_eq!(table.to_prm_string(), CAR);
+}
+
+#[test]
+fn too_many_mechoses_is_an_error() {
+    let text = CAR.replacen("3\n", "2\n", 1);
+    let e = TableMechos::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Mechos(MechosParseError::CountersMismatch {
+            declared: 4,
+            found: 5
+        })
+    ));
+    // первый мехос сверх счётчиков
+    assert_eq!(e.span.line, 9);
+    assert_eq!(e.span.token, "Constructor");
+}
+
+#[test]
+fn too_few_mechoses_is_an_error() {
+    let text = CAR.replacen("1\n1\n", "1\n4\n", 1);
+    let e = TableMechos::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Mechos(MechosParseError::CountersMismatch {
+            declared: 8,
+            found: 5
+        })
+    ));
+    assert_eq!(e.span.line, 2);
+}
+
+#[test]
+fn moving_a_mechos_keeps_counters_in_sync() {
+    let mut table = TableMechos::parse_str(CAR).unwrap();
+
+    let mechos = table.remove(1);
+    table.push(MechosGroup::Constructor, mechos).unwrap();
+
+    let counters = table.counters();
+    assert_eq!(
+        (counters.main, counters.raffa, counters.constructor),
+        (2, 1, 2)
+    );
+    assert_eq!(
+        names(table.mechoses_of(MechosGroup::Constructor)),
+        ["Constructor", "Dead-Eye"]
+    );
+
+    let text = table.to_prm_string();
+    assert!(text.starts_with("uniVang-ParametersFile_Ver_1\n2\n1\n2\n"));
+    let again = TableMechos::parse_str(&text).unwrap();
+    assert_eq!(
+        names(again.mechoses_of(MechosGroup::Main)),
+        ["Oxidize", "Last-Moggy"]
+    );
+
+    let mechos = table.remove(4);
+    table.push(MechosGroup::Main, mechos).unwrap();
+    assert_eq!(
+        names(table.mechoses()),
+        ["Oxidize", "Last-Moggy", "Dead-Eye", "Raffa", "Constructor"]
+    );
+    assert_eq!(table.counters().main, 3);
+}
+
+#[test]
+fn counters_that_overflow_are_an_error() {
+    let text = CAR.replacen("3\n1\n1\n", "18446744073709551615\n1\n0\n", 1);
+    let recovered = TableMechos::parse_str_recovering(&text).unwrap();
+
+    let e = &recovered.diagnostics[0];
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Mechos(MechosParseError::CountersOverflow)
+    ));
+    assert_eq!((e.span.line, e.span.token.as_str()), (3, "1"));
+    assert_eq!(recovered.diagnostics.len(), 1);
+
+    // счётчики сброшены, мехосы на месте
+    let table = recovered.table;
+    assert_eq!(table.counters(), MechosCounters::default());
+    assert_eq!(table.mechoses().len(), 5);
+    assert!(table
+        .groups()
+        .all(|group| group == MechosGroup::Constructor));
+}
+
+#[test]
+fn push_that_overflows_the_counters_is_an_error() {
+    let text = CAR.replacen("3\n1\n1\n", "18446744073709551615\n0\n0\n", 1);
+    let mut table = TableMechos::parse_str_recovering(&text).unwrap().table;
+    assert_eq!(table.counters().main, usize::MAX);
+
+    let mechos = TableMechos::parse_str(CAR).unwrap().remove(0);
+    assert!(matches!(
+        table.push(MechosGroup::Raffa, mechos),
+        Err(MechosParseError::CountersOverflow)
+    ));
+    assert_eq!(table.counters().raffa, 0);
+    assert_eq!(table.mechoses().len(), 5);
+}
@@ -0,0 +1,93 @@
+use vangers_prm::*;
+
+// блоки и миры нарочно не по алфавиту
+const PRICES: &str = "uniVang-ParametersFile_Ver_1
+Threall
+Nymbos 10 5
+Podish
+Phlegma 30 15
+Bunch
+Heroin 1 1
+Incubator
+Shrub 2 2
+";
+
+const TABUTASKS: &str = "uniVang-ParametersFile_Ver_1
+Podish
+100 5 0 1 2 Nymbos 3
+Incubator
+50 1 2 2 0 Phlegma 10
+B-Zone
+10 0 0 0 0 Heroin 1
+";
+
+const VANGERS: &str = "uniVang-ParametersFile_Ver_1
+40
+Necross 3
+Fostral 10
+Xplo 1
+Glorx 5
+";
+
+#[test]
+fn price_blocks_keep_file_order() {
+    let table = TablePrice::parse_str(PRICES).unwrap();
+    let escaves: Vec<_> = table.prices.keys().map(String::as_str).collect();
+    assert_eq!(escaves, ["Threall", "Podish", "Bunch", "Incubator"]);
+    assert_eq!(table.to_prm_string(), PRICES);
+}
+
+#[test]
+fn tabutask_blocks_keep_file_order() {
+    let table = TableTabutask::parse_str(TABUTASKS).unwrap();
+    let escaves: Vec<_> = table.tabutasks.keys().map(String::as_str).collect();
+    assert_eq!(escaves, ["Podish", "Incubator", "B-Zone"]);
+    assert_eq!(table.to_prm_string(), TABUTASKS);
+}
+
+#[test]
+fn vangers_weights_keep_file_order() {
+    let mut table = TableVangersWeight::parse_str(VANGERS).unwrap();
+    let worlds: Vec<_> = table.weights.keys().map(String::as_str).collect();
+    assert_eq!(worlds, ["Necross", "Fostral", "Xplo", "Glorx"]);
+    assert_eq!(table.to_prm_string(), VANGERS);
+
+    // правка веса не двигает мир, новый мир дописывается в конец
+    table.weights["Fostral"] = 7;
+    table.weights.insert("Khox".to_owned(), 2);
+    assert_eq!(
+        table.to_prm_string(),
+        VANGERS.replace("Fostral 10", "Fostral 7") + "Khox 2\n"
+    );
+}
+
+#[test]
+fn repeated_price_block_is_reported() {
+    let text = format!("{}Podish\nShrub 7 7\n", PRICES);
+    let e = TablePrice::parse_str(&text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Price(PriceParseError::DuplicateTitle)
+    ));
+    assert_eq!((e.span.line, e.span.token.as_str()), (10, "Podish"));
+
+    // первый блок остаётся, повторный пропускается целиком
+    let recovered = TablePrice::parse_str_recovering(&text).unwrap();
+    assert_eq!(recovered.diagnostics.len(), 1);
+    assert_eq!(recovered.table.to_prm_string(), PRICES);
+}
+
+#[test]
+fn repeated_vangers_world_is_reported() {
+    let text = format!("{}Fostral 7\n", VANGERS);
+    let e = TableVangersWeight::parse_str(&text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::VangersWeight(VangersWeightParseError::DuplicateWorld)
+    ));
+    assert_eq!(e.span.line, 7);
+
+    let recovered = TableVangersWeight::parse_str_recovering(&text).unwrap();
+    assert_eq!(recovered.diagnostics.len(), 1);
+    assert_eq!(recovered.table.weights["Fostral"], 10);
+}
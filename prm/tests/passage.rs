@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use vangers_prm::*;
+
+const PASSAGES: &str = include_str!("fixtures/passages.prm");
+
+fn names<'t>(passages: impl Iterator<Item = &'t Passage>) -> Vec<&'t str> {
+    passages.map(|p| p.name.as_str()).collect()
+}
+
+#[test]
+fn reads_every_passage() {
+    let table = TablePassage::parse_str(PASSAGES).unwrap();
+
+    assert_eq!(
+        names(table.passages().iter()),
+        [
+            "FostralGlorx",
+            "GlorxFostral",
+            "FostralNecross",
+            "Glorx Spot"
+        ]
+    );
+
+    let passage = table.get("FostralNecross").unwrap();
+    assert_eq!(passage.world_src_name, "Fostral");
+    assert_eq!(passage.world_dest_name, "Necross");
+    assert_eq!((passage.pos_x, passage.pos_y), (1500, 9000));
+
+    assert_eq!(table.get("Glorx Spot").unwrap().pos_x, 640);
+    assert!(table.get("Nowhere").is_none());
+}
+
+#[test]
+fn finds_passages_by_world() {
+    let table = TablePassage::parse_str(PASSAGES).unwrap();
+
+    assert_eq!(
+        names(table.leaving("Fostral")),
+        ["FostralGlorx", "FostralNecross"]
+    );
+    assert_eq!(names(table.entering("Fostral")), ["GlorxFostral"]);
+    assert!(table.entering("Boozeena").next().is_none());
+}
+
+#[test]
+fn writes_back_the_same_file() {
+    let mut table = TablePassage::parse_str(PASSAGES).unwrap();
+    let text = table.to_prm_string();
+    assert_eq!(
+        TablePassage::parse_str(&text).unwrap().to_prm_string(),
+        text
+    );
+
+    table.passages_mut().remove(0);
+    let again = TablePassage::parse_str(&table.to_prm_string()).unwrap();
+    assert_eq!(again.passages().len(), 3);
+}
+
+#[test]
+fn reports_the_wrong_field() {
+    let text = PASSAGES.replace("1800 300", "1800 north");
+    let e = TablePassage::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Passage(PassageParseError::PosY)
+    ));
+    assert_eq!((e.span.line, e.span.column), (4, 33));
+}
+
+#[test]
+fn resource_set_loads_passages() {
+    let mut files = HashMap::new();
+    files.insert("passages.prm".to_owned(), PASSAGES.as_bytes().to_vec());
+
+    let set = ResourceSet::load(&files).unwrap();
+    let passages = set.passages.as_ref().unwrap();
+    assert_eq!(passages.passages().len(), 4);
+    assert!(set.spots.is_none());
+
+    let files = set.to_files().unwrap();
+    assert_eq!(
+        TablePassage::parse_bytes(&files["passages.prm"])
+            .unwrap()
+            .passages()
+            .len(),
+        4
+    );
+}
@@ -0,0 +1,55 @@
+use std::io::Cursor;
+
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+Fostral 2048 16384
+Glorx 2048 16384
+Necross 2048 16384
+";
+
+fn names(table: &TableWorld) -> Vec<&str> {
+    table.worlds().iter().map(|w| w.name.as_str()).collect()
+}
+
+#[test]
+fn parses_from_str_reader_and_bytes() {
+    let from_str = TableWorld::parse_str(WORLDS).unwrap();
+    let from_reader = TableWorld::parse_reader(Cursor::new(WORLDS)).unwrap();
+    let from_bytes = TableWorld::parse_bytes(WORLDS.as_bytes()).unwrap();
+
+    assert_eq!(names(&from_str), ["Fostral", "Glorx", "Necross"]);
+    assert_eq!(names(&from_reader), names(&from_str));
+    assert_eq!(names(&from_bytes), names(&from_str));
+    assert_eq!(from_str.worlds()[1].width, 2048);
+    assert_eq!(from_str.worlds()[1].height, 16384);
+}
+
+#[test]
+fn checks_the_signature() {
+    let text = WORLDS.replace("uniVang-ParametersFile_Ver_1", "Fostral 1 2");
+    let e = TableWorld::parse_str(&text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::OpenFile(PrmOpenError::WrongSignature(_))
+    ));
+    assert_eq!(e.span.line, 1);
+
+    let e = TableWorld::parse_bytes(b"").err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::OpenFile(PrmOpenError::MissingSignature)
+    ));
+}
+
+#[test]
+fn reports_errors_with_the_table_file_name() {
+    let text = WORLDS.replace("Glorx 2048", "Glorx wide");
+    let e = TableWorld::parse_reader(Cursor::new(text)).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::World(WorldParseError::Width)
+    ));
+    assert_eq!(e.span.to_string(), "worlds.prm:3:7");
+}
@@ -0,0 +1,90 @@
+use vangers_prm::*;
+
+const BUNCHES: &str = include_str!("fixtures/bunches.prm");
+
+fn escaves(table: &TableBunch) -> Vec<&str> {
+    table
+        .bunches
+        .iter()
+        .map(|b| b.escave_name.as_str())
+        .collect()
+}
+
+#[test]
+fn clean_file_has_no_diagnostics() {
+    let recovered = TableBunch::parse_str_recovering(BUNCHES).unwrap();
+    assert!(recovered.is_clean());
+    assert_eq!(
+        escaves(&recovered.table),
+        ["Podish", "Incubator", "Lampasso"]
+    );
+    assert_eq!(recovered.table.bunches[0].cycles(), 2);
+}
+
+#[test]
+fn collects_every_error_of_the_file() {
+    let text = BUNCHES
+        .replace("1000 60 100", "1000 sixty 100")
+        .replace("RACE Podish Heroin 5", "RACE Podish Heroin five")
+        .replace("1200 50 110", "1200 50 110 extra");
+    let recovered = TableBunch::parse_str_recovering(&text).unwrap();
+
+    let lines: Vec<_> = recovered.diagnostics.iter().map(|e| e.span.line).collect();
+    assert_eq!(lines, [5, 11, 15]);
+
+    // неверные циклы пропущены, банчи на месте
+    let table = recovered.table;
+    assert_eq!(escaves(&table), ["Podish", "Incubator", "Lampasso"]);
+    assert_eq!(table.bunches[0].cycles(), 1);
+    assert_eq!(table.bunches[1].cycles(), 1);
+    assert_eq!(table.bunches[2].cycles(), 0);
+
+    // строгий разбор возвращает первую из ошибок
+    let e = TableBunch::parse_str(&text).err().unwrap();
+    assert_eq!(e.span.line, 5);
+}
+
+#[test]
+fn resynchronises_at_the_next_bunch_title() {
+    let text = BUNCHES.replace("Incubator 1 2", "Incubator 7 2");
+    let recovered = TableBunch::parse_str_recovering(&text).unwrap();
+
+    assert_eq!(recovered.diagnostics.len(), 1);
+    assert!(matches!(
+        recovered.diagnostics[0].kind,
+        PrmErrorKind::Bunch(BunchParseError::Title)
+    ));
+    assert_eq!(escaves(&recovered.table), ["Podish", "Lampasso"]);
+}
+
+#[test]
+fn short_block_stops_before_the_next_title() {
+    // у Podish заявлено три цикла, а описано два
+    let text = BUNCHES.replace("Podish 0 2", "Podish 0 3");
+    let recovered = TableBunch::parse_str_recovering(&text).unwrap();
+
+    assert_eq!(recovered.diagnostics.len(), 1);
+    assert_eq!(recovered.diagnostics[0].span.line, 9);
+    assert_eq!(
+        escaves(&recovered.table),
+        ["Podish", "Incubator", "Lampasso"]
+    );
+}
+
+#[test]
+fn price_blocks_resynchronise_at_the_next_title() {
+    let text = "uniVang-ParametersFile_Ver_1
+Nymbos 10 20
+Podish
+Nymbos 10 20
+Heroin ten 30
+Incubator
+Shrub 5 6
+";
+    let recovered = TablePrice::parse_str_recovering(text).unwrap();
+
+    let kinds: Vec<_> = recovered.diagnostics.iter().map(|e| e.span.line).collect();
+    assert_eq!(kinds, [2, 5]);
+    assert_eq!(recovered.table.prices["Podish"].len(), 1);
+    assert_eq!(recovered.table.prices["Incubator"][0].name, "Shrub");
+}
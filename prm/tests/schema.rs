@@ -0,0 +1,98 @@
+#![cfg(feature = "schema")]
+
+use schemars::Schema;
+use vangers_prm::*;
+
+/// Варианты перечисления из `$defs` схемы
+fn variants<'a>(schema: &'a Schema, name: &str) -> Vec<&'a str> {
+    schema.as_value()["$defs"][name]["enum"]
+        .as_array()
+        .unwrap_or_else(|| panic!("no enum {}", name))
+        .iter()
+        .map(|variant| variant.as_str().unwrap())
+        .collect()
+}
+
+/// Границы `minimum` и `maximum` поля `field`
+fn range(schema: &Schema, field: &str) -> (i64, i64) {
+    let field = &schema.as_value()["properties"][field];
+    (
+        field["minimum"].as_i64().unwrap(),
+        field["maximum"].as_i64().unwrap(),
+    )
+}
+
+#[test]
+fn enums_list_their_variant_names() {
+    let schemas = json_schemas();
+    assert_eq!(
+        variants(&schemas["mechos"], "Type"),
+        ["raffa", "light", "microbus", "atw", "track", "special"]
+    );
+    assert_eq!(
+        variants(&schemas["bunch"], "Bios"),
+        ["eleepods", "beeboorats", "zeexes"]
+    );
+}
+
+#[test]
+fn integers_are_bounded_by_their_type() {
+    let schemas = json_schemas();
+    assert_eq!(range(&schemas["mechos"], "speed"), (0, u32::MAX.into()));
+    assert_eq!(
+        range(&schemas["item"], "param1"),
+        (i32::MIN.into(), i32::MAX.into())
+    );
+
+    let slot = &schemas["mechos"].as_value()["properties"]["box"]["prefixItems"][0];
+    assert_eq!(slot["minimum"].as_i64(), Some(0));
+    assert_eq!(slot["maximum"].as_i64(), Some(u8::MAX.into()));
+}
+
+#[test]
+fn every_row_has_a_schema() {
+    let names: Vec<_> = json_schemas().keys().copied().collect();
+    assert_eq!(
+        names,
+        [
+            "mechos",
+            "item",
+            "bunch",
+            "cult",
+            "cult_stage",
+            "cult_game",
+            "location",
+            "passage",
+            "price",
+            "tabutask",
+            "world",
+            "vangers_weight"
+        ]
+    );
+}
+
+#[test]
+fn resource_set_schema_describes_every_table() {
+    let schema = ResourceSet::json_schema();
+    let properties = schema.as_value()["properties"].as_object().unwrap();
+    for table in [
+        "mechos",
+        "items",
+        "worlds",
+        "passages",
+        "escaves",
+        "spots",
+        "prices",
+        "bunches",
+        "tabutasks",
+        "vangers",
+    ]
+    .iter()
+    {
+        assert!(properties.contains_key(*table), "no table {}", table);
+    }
+    assert_eq!(
+        variants(&schema, "Bios"),
+        ["eleepods", "beeboorats", "zeexes"]
+    );
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "json")]
+
+use serde_json::json;
+use vangers_prm::*;
+
+const CAR: &str = "uniVang-ParametersFile_Ver_1
+1
+0
+0
+Oxidize 1 100 50 1 1 1 0 10 20 30 1 1 1 0 0 0 0 5 0
+";
+
+#[test]
+fn bunch_enums_are_tagged() {
+    let table = TableBunch::parse_str(include_str!("fixtures/bunches.prm")).unwrap();
+    let value = serde_json::to_value(&table).unwrap();
+
+    let podish = &value["bunches"][0];
+    assert_eq!(podish["bios"], "eleepods");
+    assert_eq!(podish["escave_name"], "Podish");
+    assert_eq!(podish["cults"][0]["game"], serde_json::Value::Null);
+    assert_eq!(
+        podish["cults"][1],
+        json!({
+            "stage": {
+                "name": "Waace Ticks",
+                "cirt": 1500,
+                "time": 40,
+                "price": 120,
+                "palette": "resource/pal/cycle1.pal"
+            },
+            "game": {
+                "type": "harvest",
+                "goods_type_name": "Nymbos",
+                "goods_count": 20,
+                "destination_name": "Incubator",
+                "rotten_goods_type_name": "Rotten"
+            }
+        })
+    );
+    assert_eq!(value["bunches"][1]["cults"][0]["game"]["type"], "race");
+
+    let again: TableBunch = serde_json::from_value(value).unwrap();
+    assert_eq!(again.to_prm_string(), table.to_prm_string());
+}
+
+#[test]
+fn item_fields_have_stable_names() {
+    let table =
+        TableItem::parse_str("uniVang-ParametersFile_Ver_1\n1\nNymbos 1 2 3 4 5 6 7\n").unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&table).unwrap(),
+        json!({
+            "items": [{
+                "name": "Nymbos",
+                "type": 1,
+                "steeler": { "full": 2, "empty": 3 },
+                "size": 4,
+                "count": 5,
+                "param1": 6,
+                "param2": 7
+            }]
+        })
+    );
+}
+
+#[test]
+fn mechos_type_and_private_fields_round_trip() {
+    let table = TableMechos::parse_str(CAR).unwrap();
+    let value = serde_json::to_value(&table).unwrap();
+
+    assert_eq!(
+        value["counters"],
+        json!({ "main": 1, "raffa": 0, "constructor": 0 })
+    );
+    assert_eq!(value["mechoses"][0]["name"], "Oxidize");
+    assert_eq!(value["mechoses"][0]["type"], "light");
+    assert_eq!(
+        serde_json::to_value(CultGameType::Race).unwrap(),
+        json!("race")
+    );
+
+    let again: TableMechos = serde_json::from_value(value).unwrap();
+    assert_eq!(again.to_prm_string(), CAR);
+}
+
+#[test]
+fn unknown_enum_names_are_rejected() {
+    let e = serde_json::from_value::<Bios>(json!("Eleepods"))
+        .err()
+        .unwrap();
+    assert!(e.to_string().contains("unknown variant"));
+    assert_eq!(
+        serde_json::from_value::<Bios>(json!("zeexes")).unwrap(),
+        Bios::Zeexes
+    );
+}
+
+#[test]
+fn document_keeps_its_encoding() {
+    let bytes = Encoding::Cp1251
+        .encode("uniVang-ParametersFile_Ver_1\r\nФострал 2048 16384\r\n")
+        .unwrap();
+    let document = PrmDocument::decode("worlds.prm", &bytes, None).table;
+
+    let value = serde_json::to_value(&document).unwrap();
+    assert_eq!(value["encoding"], "cp1251");
+
+    let again: PrmDocument = serde_json::from_value(value).unwrap();
+    assert_eq!(again.encoding(), Encoding::Cp1251);
+    assert_eq!(again.to_bytes().unwrap(), &bytes[..]);
+}
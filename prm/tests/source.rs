@@ -0,0 +1,80 @@
+use std::{collections::HashMap, fs};
+
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1\nFostral 2048 16384\n";
+
+fn is_not_found(e: &PrmParseError) -> bool {
+    matches!(
+        &e.kind,
+        PrmErrorKind::OpenFile(PrmOpenError::IO(e)) if e.kind() == std::io::ErrorKind::NotFound
+    )
+}
+
+#[test]
+fn loads_from_a_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("worlds.prm"), WORLDS).unwrap();
+
+    let source = DirSource::new(dir.path());
+    let table = TableWorld::load(&source).unwrap();
+    assert_eq!(table.worlds()[0].name, "Fostral");
+
+    let e = TableItem::load(&source).err().unwrap();
+    assert!(is_not_found(&e));
+}
+
+#[test]
+fn loads_from_memory() {
+    let mut files = HashMap::new();
+    files.insert("worlds.prm".to_owned(), WORLDS.as_bytes().to_vec());
+
+    let table = TableWorld::load(&files).unwrap();
+    assert_eq!(table.worlds()[0].width, 2048);
+    assert!(is_not_found(&TableItem::load(&files).err().unwrap()));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn loads_from_a_zip_archive() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mod.zip");
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("resource/worlds.prm", options).unwrap();
+    zip.write_all(WORLDS.as_bytes()).unwrap();
+    zip.finish().unwrap();
+
+    let root = ZipSource::new(&path).unwrap();
+    assert!(is_not_found(&TableWorld::load(&root).err().unwrap()));
+
+    let source = ZipSource::new(&path).unwrap().with_folder("resource/");
+    let table = TableWorld::load(&source).unwrap();
+    assert_eq!(table.worlds()[0].name, "Fostral");
+
+    // файл длиннее ограничения архива не обрезается, а не открывается,
+    // даже если ограничения таблицы его пропускают
+    let limited = ZipSource::new(&path)
+        .unwrap()
+        .with_folder("resource")
+        .with_max_file_size(8);
+    let e = TableWorld::load_limited(&limited, Limits::unlimited())
+        .err()
+        .unwrap();
+    assert!(matches!(
+        &e.kind,
+        PrmErrorKind::OpenFile(PrmOpenError::IO(e)) if e.kind() == std::io::ErrorKind::InvalidData
+    ));
+    assert_eq!(e.span.file, "worlds.prm");
+
+    // файл ровно по ограничению читается целиком
+    let exact = ZipSource::new(&path)
+        .unwrap()
+        .with_folder("resource")
+        .with_max_file_size(WORLDS.len());
+    let table = TableWorld::load_limited(&exact, Limits::unlimited()).unwrap();
+    assert_eq!(table.worlds()[0].height, 16384);
+}
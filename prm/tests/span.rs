@@ -0,0 +1,49 @@
+use vangers_prm::*;
+
+const ITEMS: &str = "uniVang-ParametersFile_Ver_1
+// количество предметов
+2
+/* многострочный
+   комментарий */
+Nymbos 1 0 0 1 1 0 0
+Phlegma 1 0 0 1 1 x 0
+";
+
+#[test]
+fn error_points_at_original_line_column_and_token() {
+    let e = TableItem::parse_str(ITEMS).err().unwrap();
+
+    assert!(matches!(e.kind, PrmErrorKind::Item(ItemParseError::Param1)));
+    assert_eq!(e.span.file, "item.prm");
+    // строки комментариев учитываются в номере строки
+    assert_eq!(e.span.line, 7);
+    assert_eq!(e.span.column, 19);
+    assert_eq!(e.span.token, "x");
+    assert_eq!(
+        e.to_string(),
+        "item.prm:7:19: item parse error: `param1` property (at `x`)"
+    );
+}
+
+#[test]
+fn missing_field_points_past_the_end_of_the_row() {
+    let text = ITEMS.replace("Phlegma 1 0 0 1 1 x 0", "Phlegma 1 0 0 1 1 0");
+    let e = TableItem::parse_str(&text).err().unwrap();
+
+    assert!(matches!(e.kind, PrmErrorKind::Item(ItemParseError::Param2)));
+    assert_eq!(e.span.line, 7);
+    assert_eq!(e.span.column, 20);
+    assert_eq!(e.span.token, "");
+}
+
+#[test]
+fn mechos_price_and_price_row_are_distinct_types() {
+    let price = MechosPrice { buy: 10, sell: 5 };
+    assert_eq!((price.buy, price.sell), (10, 5));
+
+    let table =
+        TablePrice::parse_str("uniVang-ParametersFile_Ver_1\nPodish\nNymbos 10 20\n").unwrap();
+    let prices: &Vec<Price> = &table.prices["Podish"];
+    assert_eq!(prices[0].name, "Nymbos");
+    assert_eq!((prices[0].buy, prices[0].sell), (10, 20));
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use vangers_prm::*;
+
+const SPOTS: &str = include_str!("fixtures/spot.prm");
+
+fn spots(text: &str) -> Result<Vec<Spot>, PrmParseError> {
+    let mut files = HashMap::new();
+    files.insert("spot.prm".to_owned(), text.as_bytes().to_vec());
+    let set = ResourceSet::load(&files)?;
+    Ok(set.spots.unwrap().spots)
+}
+
+fn goods(spot: &Spot) -> Vec<(&str, &str)> {
+    spot.goods
+        .iter()
+        .map(|(item, dest)| (item.as_str(), dest.as_str()))
+        .collect()
+}
+
+#[test]
+fn reads_goods_of_every_spot() {
+    let spots = spots(SPOTS).unwrap();
+
+    let names: Vec<_> = spots.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Threall", "B-Zone", "Spobs"]);
+
+    assert_eq!(
+        goods(&spots[0]),
+        [("Cirtainer", "Podish"), ("Cirtainer", "Incubator")]
+    );
+    assert_eq!(spots[1].world_name, "Necross");
+    assert_eq!(
+        goods(&spots[1]),
+        [
+            ("Rubbox", "Lampasso"),
+            ("Conlarver", "Ogorod"),
+            ("Kernoboo", "Podish")
+        ]
+    );
+    assert!(spots[2].goods.is_empty());
+}
+
+#[test]
+fn block_without_none_is_an_error() {
+    let text = SPOTS.replacen("Cirtainer Incubator\nnone\n", "Cirtainer Incubator\n", 1);
+    let e = spots(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Location(LocationParseError::ExpectedTerminateLine)
+    ));
+    assert_eq!(e.span.line, 6);
+}
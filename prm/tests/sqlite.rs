@@ -0,0 +1,117 @@
+#![cfg(feature = "sqlite")]
+
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+use vangers_prm::*;
+
+const WORLDS: &str = "uniVang-ParametersFile_Ver_1
+Fostral 2048 16384
+Glorx 2048 8192
+Necross 2048 8192
+Threall 1024 1024
+Boozeena 512 512
+";
+
+fn set(worlds: Option<&str>) -> ResourceSet {
+    let mut files = HashMap::new();
+    let mut add = |name: &str, bytes: &[u8]| {
+        files.insert(name.to_owned(), bytes.to_vec());
+    };
+    add("bunches.prm", include_bytes!("fixtures/bunches.prm"));
+    add("escaves.prm", include_bytes!("fixtures/escaves.prm"));
+    add("spot.prm", include_bytes!("fixtures/spot.prm"));
+    add("passages.prm", include_bytes!("fixtures/passages.prm"));
+    if let Some(worlds) = worlds {
+        add("worlds.prm", worlds.as_bytes());
+    }
+    ResourceSet::load(&files).unwrap()
+}
+
+fn count(connection: &Connection, table: &str) -> i64 {
+    connection
+        .query_row(&format!("SELECT count(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+}
+
+#[test]
+fn set_is_written_with_its_references() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    set(Some(WORLDS)).write_sqlite(&mut connection).unwrap();
+
+    assert_eq!(count(&connection, "worlds"), 5);
+    assert_eq!(count(&connection, "locations"), 7);
+    assert_eq!(count(&connection, "cults"), 5);
+    assert_eq!(count(&connection, "cult_games"), 2);
+}
+
+#[test]
+fn missing_reference_is_an_error() {
+    let worlds = WORLDS.replace("Boozeena 512 512\n", "");
+    let mut connection = Connection::open_in_memory().unwrap();
+
+    match set(Some(&worlds)).write_sqlite(&mut connection) {
+        Err(SqliteError::ForeignKey {
+            table,
+            column,
+            value,
+            parent,
+        }) => {
+            assert_eq!(
+                (
+                    table.as_str(),
+                    column.as_str(),
+                    value.as_str(),
+                    parent.as_str()
+                ),
+                ("spots", "world", "`Boozeena`", "worlds")
+            );
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    // транзакция откатывается
+    let tables: i64 = connection
+        .query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(tables, 0);
+}
+
+#[test]
+fn references_to_absent_files_are_not_checked() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    set(None).write_sqlite(&mut connection).unwrap();
+    assert_eq!(count(&connection, "worlds"), 0);
+    assert_eq!(count(&connection, "escaves"), 4);
+}
+
+fn duplicate_name(set: &ResourceSet) -> (String, String) {
+    let mut connection = Connection::open_in_memory().unwrap();
+    match set.write_sqlite(&mut connection) {
+        Err(SqliteError::DuplicateName { table, name }) => (table, name),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn duplicate_names_stop_the_export() {
+    // разборщик повтор пропускает, выгрузка — нет
+    let worlds = format!("{}Glorx 10 10\n", WORLDS);
+    let table = TableWorld::parse_str(&worlds).unwrap();
+    assert_eq!(table.worlds().len(), 6);
+    assert_eq!(
+        duplicate_name(&set(Some(&worlds))),
+        ("worlds".to_owned(), "Glorx".to_owned())
+    );
+
+    // эскейв и спот с одним названием
+    let mut set = set(Some(WORLDS));
+    let spots = include_str!("fixtures/spot.prm").replace("Threall Threall", "Podish Threall");
+    set.spots = Some(TableSpot::parse_str(&spots).unwrap());
+    assert_eq!(
+        duplicate_name(&set),
+        ("locations".to_owned(), "Podish".to_owned())
+    );
+}
@@ -0,0 +1,135 @@
+use vangers_prm::*;
+
+const TABUTASK: &str = "uniVang-ParametersFile_Ver_1
+Podish
+100 5 0 1 2 Nymbos 3
+250 -2 1 0 1 \"Eleepod Bath\" 1
+Incubator
+50 1 2 2 0 Phlegma 10
+";
+
+#[test]
+fn parses_tasks_per_escave_block() {
+    let table = TableTabutask::parse_str(TABUTASK).unwrap();
+
+    let escaves: Vec<_> = table.tabutasks.keys().map(String::as_str).collect();
+    assert_eq!(escaves, ["Podish", "Incubator"]);
+
+    let podish = &table.tabutasks["Podish"];
+    assert_eq!(podish.len(), 2);
+
+    let task = &podish[1];
+    assert_eq!(task.cash, 250);
+    assert_eq!(task.luck, -2);
+    assert_eq!(task.cycle, 1);
+    assert_eq!(task.target, 0);
+    assert_eq!(task.work, 1);
+    assert_eq!(task.item, "Eleepod Bath");
+    assert_eq!(task.count, 1);
+
+    let incubator = &table.tabutasks["Incubator"];
+    assert_eq!(incubator.len(), 1);
+    assert_eq!(incubator[0].item, "Phlegma");
+    assert_eq!(incubator[0].count, 10);
+}
+
+#[test]
+fn writes_back_the_same_file() {
+    let table = TableTabutask::parse_str(TABUTASK).unwrap();
+    assert_eq!(table.to_prm_string(), TABUTASK);
+}
+
+#[test]
+fn reports_the_wrong_field() {
+    let text = TABUTASK.replace("100 5 0", "100 lucky 0");
+    let e = TableTabutask::parse_str(&text).err().unwrap();
+
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Tabutask(TabutaskParseError::Luck)
+    ));
+    assert_eq!((e.span.line, e.span.column), (3, 5));
+}
+
+#[test]
+fn keeps_target_and_work_numbers_as_is() {
+    let text = TABUTASK.replace("250 -2 1 0 1", "250 -2 1 9 7");
+    let table = TableTabutask::parse_str(&text).unwrap();
+    let task = &table.tabutasks["Podish"][1];
+    assert_eq!((task.target, task.work), (9, 7));
+    assert_eq!(table.to_prm_string(), text);
+}
+
+#[test]
+fn repeated_escave_block_is_reported() {
+    let text = format!("{}Podish\n1 0 0 0 0 Shrub 1\n", TABUTASK);
+    let e = TableTabutask::parse_str(&text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Tabutask(TabutaskParseError::DuplicateTitle)
+    ));
+    assert_eq!((e.span.line, e.span.token.as_str()), (7, "Podish"));
+
+    // первый блок остаётся, повторный пропускается целиком
+    let recovered = TableTabutask::parse_str_recovering(&text).unwrap();
+    assert_eq!(recovered.diagnostics.len(), 1);
+    assert_eq!(recovered.table.to_prm_string(), TABUTASK);
+}
+
+#[test]
+fn rejects_missing_and_extra_fields() {
+    let text = TABUTASK.replace("Phlegma 10", "Phlegma");
+    let e = TableTabutask::parse_str(&text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Tabutask(TabutaskParseError::Count)
+    ));
+
+    let text = TABUTASK.replace("Phlegma 10", "Phlegma 10 7");
+    let e = TableTabutask::parse_str(&text).err().unwrap();
+    assert!(matches!(
+        e.kind,
+        PrmErrorKind::Tabutask(TabutaskParseError::UnexpectedAdditionalParameter)
+    ));
+}
+
+#[test]
+fn recovers_from_bad_rows() {
+    let text = TABUTASK
+        .replace(
+            "Podish\n",
+            "1 2 3 4 5 Nymbos 6\n7 8 9 1 2 Nymbos 3\nPodish\n",
+        )
+        .replace("Phlegma 10", "Phlegma ten");
+    let recovered = TableTabutask::parse_str_recovering(&text).unwrap();
+
+    // задания до первого заголовка — одна ошибка на весь пропущенный кусок
+    let kinds: Vec<_> = recovered
+        .diagnostics
+        .iter()
+        .map(|e| (e.span.line, e.kind.to_string()))
+        .collect();
+    assert_eq!(kinds.len(), 2, "{:?}", kinds);
+    assert!(matches!(
+        recovered.diagnostics[0].kind,
+        PrmErrorKind::Tabutask(TabutaskParseError::ExpectedTitleBlock)
+    ));
+    assert!(matches!(
+        recovered.diagnostics[1].kind,
+        PrmErrorKind::Tabutask(TabutaskParseError::Count)
+    ));
+
+    let table = recovered.table;
+    assert_eq!(table.tabutasks["Podish"].len(), 2);
+    assert!(table.tabutasks["Incubator"].is_empty());
+}
+
+#[test]
+fn resource_set_loads_tabutasks() {
+    let mut files = std::collections::HashMap::new();
+    files.insert("tabutask.prm".to_owned(), TABUTASK.as_bytes().to_vec());
+
+    let set = ResourceSet::load(&files).unwrap();
+    assert_eq!(set.tabutasks.as_ref().unwrap().tabutasks.len(), 2);
+    assert_eq!(set.to_files().unwrap()["tabutask.prm"], TABUTASK.as_bytes());
+}
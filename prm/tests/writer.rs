@@ -0,0 +1,238 @@
+use vangers_prm::*;
+
+/// Разбирает `text` и проверяет, что таблица записывается как `expected`,
+/// а записанный текст разбирается в ту же таблицу
+fn assert_written<T: PrmFile + PrmWrite>(text: &str, expected: &str) {
+    let table = T::parse_str(text).unwrap();
+    assert_eq!(table.to_prm_string(), expected);
+
+    let again = T::parse_str(expected).unwrap();
+    assert_eq!(again.to_prm_string(), expected);
+}
+
+#[test]
+fn item_count_title_follows_items() {
+    let text = "uniVang-ParametersFile_Ver_1
+// число предметов
+2
+Nymbos   1 0 0 1 1 0 0 // комментарий
+\"Eleepod Bath\" 2 -1 3 4 5 6 7
+";
+    assert_written::<TableItem>(
+        text,
+        "uniVang-ParametersFile_Ver_1
+2
+Nymbos 1 0 0 1 1 0 0
+\"Eleepod Bath\" 2 -1 3 4 5 6 7
+",
+    );
+
+    let mut table = TableItem::parse_str(text).unwrap();
+    table.items.pop();
+    assert!(table
+        .to_prm_string()
+        .starts_with("uniVang-ParametersFile_Ver_1\n1\nNymbos "));
+}
+
+#[test]
+fn car_counters_are_written_first() {
+    let text = "uniVang-ParametersFile_Ver_1
+1 /* основные */
+0
+0
+Oxidize 1 100 50 1 1 1 0 10 20 30 1 1 1 0 0 0 0 5 0
+";
+    assert_written::<TableMechos>(
+        text,
+        "uniVang-ParametersFile_Ver_1
+1
+0
+0
+Oxidize 1 100 50 1 1 1 0 10 20 30 1 1 1 0 0 0 0 5 0
+",
+    );
+}
+
+#[test]
+fn bunch_titles_and_none_terminators() {
+    let expected = "uniVang-ParametersFile_Ver_1
+Podish 0 2
+Eleerection 1000 60 100 resource/pal/cycle0.pal
+none
+\"Waace Ticks\" 1500 40 120 resource/pal/cycle1.pal
+HARVEST Nymbos 20 Incubator Rotten
+Incubator 1 2
+Gaardation 800 30 90 resource/pal/cycle2.pal
+RACE Podish Heroin 5 Lampasso Shrub 5 Rotten
+Ebb 900 35 95 resource/pal/cycle3.pal
+none
+Lampasso 2 1
+Kiss 1200 50 110 resource/pal/cycle4.pal
+none
+";
+    assert_written::<TableBunch>(include_str!("fixtures/bunches.prm"), expected);
+}
+
+#[test]
+fn escave_blocks_end_with_none() {
+    let text = "uniVang-ParametersFile_Ver_1
+Podish Fostral 100 200 none
+Nymbos Incubator
+none
+\"Incubator\" Fostral 300 400 Heroin
+none
+";
+    assert_written::<TableEscave>(
+        text,
+        "uniVang-ParametersFile_Ver_1
+Podish Fostral 100 200 none
+Nymbos Incubator
+none
+Incubator Fostral 300 400 Heroin
+none
+",
+    );
+}
+
+#[test]
+fn price_world_and_vangers_rows() {
+    assert_written::<TablePrice>(
+        "uniVang-ParametersFile_Ver_1
+Podish
+Nymbos 10 5
+\"Eleepod Bath\" 20 10
+Incubator
+Phlegma 30 15
+",
+        "uniVang-ParametersFile_Ver_1
+Podish
+Nymbos 10 5
+\"Eleepod Bath\" 20 10
+Incubator
+Phlegma 30 15
+",
+    );
+
+    assert_written::<TableWorld>(
+        "uniVang-ParametersFile_Ver_1
+Fostral\t2048 16384
+",
+        "uniVang-ParametersFile_Ver_1
+Fostral 2048 16384
+",
+    );
+
+    assert_written::<TableVangersWeight>(
+        "uniVang-ParametersFile_Ver_1
+40
+Fostral 10
+Glorx 5
+",
+        "uniVang-ParametersFile_Ver_1
+40
+Fostral 10
+Glorx 5
+",
+    );
+}
+
+#[test]
+fn saves_encoded_file_into_folder() {
+    let table = TableWorld::parse_str(
+        "uniVang-ParametersFile_Ver_1
+Фострал 2048 16384
+",
+    )
+    .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    table.file_save(dir.path()).unwrap();
+    let saved = std::fs::read_to_string(dir.path().join("worlds.prm")).unwrap();
+    assert_eq!(saved, table.to_prm_string());
+
+    let mut bytes = vec![];
+    table.write_encoded(&mut bytes, Encoding::Cp866).unwrap();
+    let again = TableWorld::parse_bytes(&bytes).unwrap();
+    assert_eq!(again.worlds()[0].name, "Фострал");
+}
+
+#[test]
+fn new_tables_are_written() {
+    let items = TableItem {
+        items: vec![Item {
+            name: "Eleepod Bath".to_owned(),
+            r#type: 2,
+            steeler: SteelerType { full: -1, empty: 3 },
+            size: 4,
+            count: 5,
+            param1: 6,
+            param2: 7,
+        }],
+    };
+    assert_eq!(
+        items.to_prm_string(),
+        "uniVang-ParametersFile_Ver_1\n1\n\"Eleepod Bath\" 2 -1 3 4 5 6 7\n"
+    );
+
+    let worlds = TableWorld::new(vec![World {
+        name: "Fostral".to_owned(),
+        width: 2048,
+        height: 16384,
+    }]);
+    assert_eq!(
+        worlds.to_prm_string(),
+        "uniVang-ParametersFile_Ver_1\nFostral 2048 16384\n"
+    );
+
+    let mut mechoses = TableMechos::new();
+    mechoses
+        .push(
+            MechosGroup::Raffa,
+            Mechos {
+                name: "Raffa".to_owned(),
+                r#type: Type::Raffa,
+                price: MechosPrice { buy: 10, sell: 5 },
+                r#box: (1, 0, 0, 0),
+                speed: 13,
+                armor: 23,
+                energy: 33,
+                energy_delta: 1,
+                energy_drop: 1,
+                drop_time: 1,
+                fire: 0,
+                water: 0,
+                oxygen: 0,
+                fly: 0,
+                damage: 1,
+                teleport: 0,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        mechoses.to_prm_string(),
+        "uniVang-ParametersFile_Ver_1\n0\n1\n0\nRaffa 0 10 5 1 0 0 0 13 23 33 1 1 1 0 0 0 0 1 0\n"
+    );
+}
+
+#[test]
+fn header_is_written_as_given() {
+    let text = "uniVang-ParametersFile_Ver_1\nFostral 2048 16384\n";
+    let rows = TableWorld::check_signature(Rows::tokenize("worlds.prm", text).unwrap()).unwrap();
+    let table: TableWorld = TableWorld::parse_rows(&rows).unwrap();
+    assert_eq!(
+        table.to_prm_string_with_header(rows.header().unwrap()),
+        text
+    );
+
+    let header = PrmHeader::univang(2);
+    assert_eq!(
+        table.to_prm_string_with_header(&header),
+        "uniVang-ParametersFile_Ver_2\nFostral 2048 16384\n"
+    );
+
+    let mut bytes = vec![];
+    table
+        .write_with_header(&mut bytes, &header, Encoding::Cp866)
+        .unwrap();
+    assert!(bytes.starts_with(b"uniVang-ParametersFile_Ver_2\n"));
+}
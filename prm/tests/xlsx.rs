@@ -0,0 +1,96 @@
+#![cfg(feature = "xlsx")]
+
+use std::io::Cursor;
+
+use rust_xlsxwriter::Workbook;
+use vangers_prm::*;
+
+/// Книга с листом `worlds`: строки — ячейки листа, первая — заголовок
+fn worlds_book(rows: &[[&str; 3]]) -> Vec<u8> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("worlds").unwrap();
+    for (row, cells) in rows.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            sheet.write_string(row as u32, col as u16, *cell).unwrap();
+        }
+    }
+    workbook.save_to_buffer().unwrap()
+}
+
+#[test]
+fn set_round_trips_through_xlsx() {
+    let mut files = std::collections::HashMap::new();
+    files.insert(
+        "worlds.prm".to_owned(),
+        b"uniVang-ParametersFile_Ver_1\nFostral 2048 16384\nGlorx 2048 8192\n".to_vec(),
+    );
+    files.insert(
+        "bunches.prm".to_owned(),
+        include_bytes!("fixtures/bunches.prm").to_vec(),
+    );
+    let set = ResourceSet::load(&files).unwrap();
+
+    let book = set.to_xlsx().unwrap();
+    let again = ResourceSet::from_xlsx(Cursor::new(book)).unwrap();
+    assert!(again.items.is_none());
+    assert_eq!(
+        again.worlds.unwrap().to_prm_string(),
+        set.worlds.unwrap().to_prm_string()
+    );
+    assert_eq!(
+        again.bunches.unwrap().to_prm_string(),
+        set.bunches.unwrap().to_prm_string()
+    );
+}
+
+#[test]
+fn all_bad_rows_are_reported_at_once() {
+    let book = worlds_book(&[
+        ["name", "width", "height"],
+        ["Fostral", "x", "16384"],
+        ["Glorx", "2048", "8192"],
+        ["Necross", "2048", "y"],
+    ]);
+
+    let errors = match ResourceSet::from_xlsx(Cursor::new(book)).err().unwrap() {
+        XlsxError::Cells(errors) => errors,
+        e => panic!("unexpected error: {}", e),
+    };
+    let places: Vec<_> = errors
+        .iter()
+        .map(|e| {
+            (
+                e.span.file.as_ref(),
+                e.span.line,
+                e.span.column,
+                e.span.token.as_ref(),
+            )
+        })
+        .collect();
+    assert_eq!(places, [("worlds", 2, 2, "x"), ("worlds", 4, 3, "y")]);
+}
+
+#[test]
+fn extra_cells_are_rejected() {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("worlds").unwrap();
+    for (col, name) in ["name", "width", "height"].iter().enumerate() {
+        sheet.write_string(0, col as u16, *name).unwrap();
+    }
+    sheet.write_string(1, 0, "Fostral").unwrap();
+    sheet.write_number(1, 1, 2048).unwrap();
+    sheet.write_number(1, 2, 16384).unwrap();
+    sheet.write_string(1, 4, "lost").unwrap();
+    let book = workbook.save_to_buffer().unwrap();
+
+    assert!(matches!(
+        ResourceSet::from_xlsx(Cursor::new(book)).err().unwrap(),
+        XlsxError::UnexpectedCell {
+            row: 2,
+            column: 5,
+            ..
+        }
+    ));
+}